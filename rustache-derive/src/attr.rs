@@ -0,0 +1,45 @@
+use syn::{Attribute, LitStr, Result};
+
+// Options that may be given to a field or variant through a
+// `#[mustache(...)]` attribute:
+//
+//   #[mustache(rename = "name")]  use `name` as the key instead of the field name
+//   #[mustache(skip)]             leave the field out of the built data entirely
+//
+// or to a struct:
+//
+//   #[mustache(template = "path")]  bind the template at `path`, relative to
+//                                   the crate root, and check it against the
+//                                   struct's fields
+#[derive(Default)]
+pub struct Options {
+    pub rename: Option<String>,
+    pub skip: bool,
+    pub template: Option<LitStr>,
+}
+
+impl Options {
+    // Collect the options from every `mustache` attribute on an item,
+    // returning an error spanned at the first unknown or malformed one
+    pub fn from_attrs(attrs: &[Attribute]) -> Result<Options> {
+        let mut options = Options::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("mustache")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    let name: LitStr = meta.value()?.parse()?;
+                    options.rename = Some(name.value());
+                    Ok(())
+                } else if meta.path.is_ident("template") {
+                    options.template = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("skip") {
+                    options.skip = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown mustache attribute"))
+                }
+            })?;
+        }
+        Ok(options)
+    }
+}
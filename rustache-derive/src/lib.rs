@@ -0,0 +1,399 @@
+#![deny(missing_docs)]
+
+//! Custom derive for the Rustache library.
+//!
+//! `#[derive(Mustache)]` implements `From<T> for rustache::Data`, and for
+//! structs with named fields `From<T> for rustache::HashBuilder`, so view
+//! models can be rendered without building the data by hand:
+//!
+//! ```rust,ignore
+//! #[derive(Mustache)]
+//! struct Hero {
+//!     name: String,
+//!     #[mustache(rename = "class")]
+//!     class_name: String,
+//!     cards: Vec<String>,
+//!     title: Option<String>,
+//!     #[mustache(skip)]
+//!     secret: String,
+//! }
+//!
+//! HashBuilder::from(hero).render("{{name}} the {{class}}", &mut out).unwrap();
+//! ```
+//!
+//! Structs with named fields become hashes, newtype structs become their
+//! inner value and tuple structs become vectors.  `Vec<T>` fields become
+//! vectors and `Option<T>` fields become `false` when they are `None`.
+//! Enums follow the usual externally tagged layout: a unit variant becomes
+//! its name as a string, any other variant a hash with the variant name as
+//! its only key.
+//!
+//! A struct with named fields may also be bound to a template, which is
+//! then checked against the struct's fields at compile time:
+//!
+//! ```rust,ignore
+//! #[derive(Mustache)]
+//! #[mustache(template = "templates/hero.html")]
+//! struct Hero {
+//!     name: String,
+//!     cards: Vec<Card>,
+//! }
+//!
+//! hero.render(&mut out).unwrap();
+//! ```
+//!
+//! The path is relative to the crate root.  Every value, unescaped and
+//! section tag in the template has to name a field of the right shape: a
+//! value tag can't name a list or a nested struct, and a section tag can't
+//! name a plain string or number.  Names used inside a section over a
+//! nested struct are checked against that struct when it derives
+//! `Mustache` as well.  Enums and types from other crates can render as
+//! anything, so names used with them aren't checked.  A mismatch is a
+//! compile error giving the template line it was found on.  Set delimiter tags such as `{{=<% %>=}}` can't
+//! be used in a checked template.
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as Tokens};
+use syn::ext::IdentExt;
+use syn::{Data, DeriveInput, Fields, GenericArgument, GenericParam, Ident, Lifetime, LitStr,
+          PathArguments, Result, Type};
+
+use attr::Options;
+use template::{Checker, Shape};
+
+/// Derive `From<T>` for `rustache::Data` (and `rustache::HashBuilder`)
+#[proc_macro_derive(Mustache, attributes(mustache))]
+pub fn derive_mustache(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => {
+            // `to_compile_error` refers to `::core`, which 2015 edition
+            // crates don't have in their root, so spell the errors out
+            let errors = err.into_iter().map(|err| {
+                let message = err.to_string();
+                quote_spanned!(err.span()=> compile_error!(#message);)
+            });
+            quote!(#(#errors)*).into()
+        }
+    }
+}
+
+fn expand(input: &DeriveInput) -> Result<Tokens> {
+    let name = &input.ident;
+    let lifetime = Lifetime::new("'__rustache", Span::call_site());
+
+    // the generated impls are generic over the lifetime of the data,
+    // and every type parameter has to be convertible into that data
+    let mut generics = input.generics.clone();
+    let type_params: Vec<Ident> = generics.type_params().map(|p| p.ident.clone()).collect();
+    {
+        let where_clause = generics.make_where_clause();
+        for param in type_params.iter() {
+            where_clause.predicates.push(syn::parse_quote! {
+                #param: ::std::convert::Into<::rustache::Data<#lifetime>>
+            });
+        }
+    }
+    generics.params.insert(0, GenericParam::Lifetime(syn::LifetimeParam::new(lifetime.clone())));
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+
+    let options = Options::from_attrs(&input.attrs)?;
+    let body = match input.data {
+        Data::Struct(ref data) => {
+            if let Fields::Named(_) = data.fields {
+                let builder = build_hash(&data.fields, |field| quote!(value.#field))?;
+                let typed = typed_template(input, &type_params, options.template.as_ref())?;
+                return Ok(quote! {
+                    #typed
+
+                    impl #impl_generics ::std::convert::From<#name #ty_generics>
+                        for ::rustache::HashBuilder<#lifetime> #where_clause
+                    {
+                        fn from(value: #name #ty_generics) -> ::rustache::HashBuilder<#lifetime> {
+                            #builder
+                        }
+                    }
+
+                    impl #impl_generics ::std::convert::From<#name #ty_generics>
+                        for ::rustache::Data<#lifetime> #where_clause
+                    {
+                        fn from(value: #name #ty_generics) -> ::rustache::Data<#lifetime> {
+                            ::rustache::HashBuilder::from(value).into()
+                        }
+                    }
+                });
+            }
+            unnamed_fields(&data.fields)?;
+            let values: Vec<Tokens> = data.fields
+                .iter()
+                .enumerate()
+                .map(|(i, field)| {
+                    let index = syn::Index::from(i);
+                    convert(quote!(value.#index), &field.ty)
+                })
+                .collect();
+            match values.len() {
+                0 => quote!(::rustache::HashBuilder::new().into()),
+                1 => values[0].clone(),
+                _ => quote!(::rustache::Data::Vector(vec![#(#values),*])),
+            }
+        }
+        Data::Enum(ref data) => {
+            let mut arms = Vec::new();
+            for variant in data.variants.iter() {
+                let options = item_options(&variant.attrs)?;
+                let ident = &variant.ident;
+                let key = options.rename.unwrap_or_else(|| ident.unraw().to_string());
+                arms.push(match variant.fields {
+                    Fields::Unit => quote! {
+                        #name::#ident => ::rustache::Data::String(#key.to_string())
+                    },
+                    Fields::Unnamed(ref fields) => {
+                        unnamed_fields(&variant.fields)?;
+                        let bindings: Vec<Ident> = (0..fields.unnamed.len())
+                            .map(|i| Ident::new(&format!("__field{}", i), Span::call_site()))
+                            .collect();
+                        let values: Vec<Tokens> = fields.unnamed
+                            .iter()
+                            .zip(bindings.iter())
+                            .map(|(field, binding)| convert(quote!(#binding), &field.ty))
+                            .collect();
+                        let inner = if values.len() == 1 {
+                            values[0].clone()
+                        } else {
+                            quote!(::rustache::Data::Vector(vec![#(#values),*]))
+                        };
+                        quote! {
+                            #name::#ident(#(#bindings),*) => {
+                                ::rustache::HashBuilder::new().insert(#key, #inner).into()
+                            }
+                        }
+                    }
+                    Fields::Named(ref fields) => {
+                        let bindings: Vec<&Ident> = fields.named
+                            .iter()
+                            .map(|field| field.ident.as_ref().unwrap())
+                            .collect();
+                        let inner = build_hash(&variant.fields, |field| quote!(#field))?;
+                        quote! {
+                            #[allow(unused_variables)]
+                            #name::#ident { #(#bindings),* } => {
+                                ::rustache::HashBuilder::new().insert(#key, #inner).into()
+                            }
+                        }
+                    }
+                });
+            }
+            quote! {
+                match value {
+                    #(#arms,)*
+                }
+            }
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(input, "Mustache cannot be derived for unions"));
+        }
+    };
+
+    if let Some(template) = options.template {
+        return Err(syn::Error::new_spanned(template,
+                                           "a template can only be bound to a struct with \
+                                            named fields"));
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::std::convert::From<#name #ty_generics>
+            for ::rustache::Data<#lifetime> #where_clause
+        {
+            fn from(value: #name #ty_generics) -> ::rustache::Data<#lifetime> {
+                #body
+            }
+        }
+    })
+}
+
+// Build a HashBuilder out of named fields, where `access` produces the
+// expression that moves each field's value out of its container
+fn build_hash<F>(fields: &Fields, access: F) -> Result<Tokens>
+    where F: Fn(&Ident) -> Tokens
+{
+    let mut inserts = Vec::new();
+    for field in fields.iter() {
+        let options = item_options(&field.attrs)?;
+        if options.skip {
+            continue;
+        }
+        let ident = field.ident.as_ref().unwrap();
+        let key = options.rename.unwrap_or_else(|| ident.unraw().to_string());
+        let value = convert(access(ident), &field.ty);
+        inserts.push(quote!(.insert(#key, #value)));
+    }
+    Ok(quote!(::rustache::HashBuilder::new() #(#inserts)*))
+}
+
+// Fields without names have no key to rename or leave out of the built
+// data, so they can't be given any options
+fn unnamed_fields(fields: &Fields) -> Result<()> {
+    for field in fields.iter() {
+        if let Some(attr) = field.attrs.iter().find(|attr| attr.path().is_ident("mustache")) {
+            return Err(syn::Error::new_spanned(attr,
+                                               "mustache attributes can only be used on \
+                                                named fields"));
+        }
+    }
+    Ok(())
+}
+
+// Record the shape of every field for checking templates that reach into
+// this struct, and when a template is bound to it, check the template and
+// attach it through `rustache::TypedTemplate`
+fn typed_template(input: &DeriveInput,
+                  type_params: &[Ident],
+                  template: Option<&LitStr>)
+                  -> Result<Tokens> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let mut fields = Vec::new();
+    if let Data::Struct(ref data) = input.data {
+        for field in data.fields.iter() {
+            let options = item_options(&field.attrs)?;
+            if options.skip {
+                continue;
+            }
+            let ident = field.ident.as_ref().unwrap();
+            let key = options.rename.unwrap_or_else(|| ident.unraw().to_string());
+            fields.push((key, Shape::of(&field.ty, type_params)));
+        }
+    }
+
+    let shapes: Vec<Tokens> = fields.iter()
+        .map(|(key, shape)| {
+            let shape = shape.field_shape();
+            quote!((#key, #shape))
+        })
+        .collect();
+    let mut tokens = quote! {
+        impl #impl_generics ::rustache::Fields for #name #ty_generics #where_clause {
+            const FIELDS: &'static [(&'static str, ::rustache::FieldShape)] = &[#(#shapes),*];
+        }
+    };
+
+    let template = match template {
+        Some(template) => template,
+        None => return Ok(tokens),
+    };
+    let root = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let path = std::path::Path::new(&root).join(template.value());
+    let source = match std::fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(err) => {
+            return Err(syn::Error::new_spanned(template,
+                                               format!("{}: {}", template.value(), err)))
+        }
+    };
+    let assertions = Checker::new(template, fields).check(&template::scan(&source))?;
+    let path = path.to_string_lossy().into_owned();
+
+    tokens.extend(quote! {
+        impl #impl_generics ::rustache::TypedTemplate for #name #ty_generics #where_clause {
+            const TEMPLATE: &'static str = include_str!(#path);
+        }
+
+        #(#assertions)*
+    });
+    Ok(tokens)
+}
+
+// Options of a field or variant, which can't carry a template
+fn item_options(attrs: &[syn::Attribute]) -> Result<Options> {
+    let options = Options::from_attrs(attrs)?;
+    if let Some(template) = options.template {
+        return Err(syn::Error::new_spanned(template,
+                                           "a template can only be bound to a struct"));
+    }
+    Ok(options)
+}
+
+// Produce the expression converting `expr` of type `ty` into `Data`.
+// `Vec<T>` and `Option<T>` are unwrapped here so their contents only
+// need to be convertible themselves.
+fn convert(expr: Tokens, ty: &Type) -> Tokens {
+    match wrapped_type(ty) {
+        Some((ref wrapper, inner)) if wrapper == "Vec" => {
+            let item = convert(quote!(item), inner);
+            quote!(::rustache::Data::Vector(#expr.into_iter().map(|item| #item).collect()))
+        }
+        Some((ref wrapper, inner)) if wrapper == "Option" => {
+            let some = convert(quote!(value), inner);
+            quote! {
+                match #expr {
+                    ::std::option::Option::Some(value) => #some,
+                    ::std::option::Option::None => ::rustache::Data::Null,
+                }
+            }
+        }
+        _ => quote!(::std::convert::Into::<::rustache::Data>::into(#expr)),
+    }
+}
+
+// Split a path type with a single type argument, like `Vec<T>` or
+// `Option<T>`, into the name of its last segment and `T`
+fn wrapped_type(ty: &Type) -> Option<(String, &Type)> {
+    let path = match *ty {
+        Type::Path(ref path) if path.qself.is_none() => &path.path,
+        _ => return None,
+    };
+    let segment = path.segments.last()?;
+    if let PathArguments::AngleBracketed(ref args) = segment.arguments {
+        if args.args.len() == 1 {
+            if let GenericArgument::Type(ref inner) = args.args[0] {
+                return Some((segment.ident.to_string(), inner));
+            }
+        }
+    }
+    None
+}
+
+// Internal Modules
+mod attr;
+mod template;
+
+#[cfg(test)]
+mod derive_tests {
+    use super::expand;
+
+    fn error(input: syn::DeriveInput) -> String {
+        match expand(&input) {
+            Ok(_) => panic!("expected an error"),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_options_on_unnamed_fields() {
+        assert_eq!("mustache attributes can only be used on named fields",
+                   error(syn::parse_quote! {
+                       struct Pair(String, #[mustache(skip)] String);
+                   }));
+        assert_eq!("mustache attributes can only be used on named fields",
+                   error(syn::parse_quote! {
+                       enum Card {
+                           Spell(#[mustache(rename = "name")] String),
+                       }
+                   }));
+        assert!(expand(&syn::parse_quote! {
+            enum Card {
+                #[mustache(rename = "spell")]
+                Spell(String),
+            }
+        }).is_ok());
+    }
+}
@@ -0,0 +1,539 @@
+// Compile time checking of templates bound to a struct with
+// `#[mustache(template = "path")]`.
+//
+// The template is scanned for its tags, and every name used by a value,
+// unescaped or section tag is resolved against the fields of the struct
+// the same way the renderer walks its context stack.  Names that can be
+// resolved here are checked right away; names that resolve into a nested
+// type are checked by const assertions against that type's `Fields` impl,
+// since a derive macro can only see the struct it is attached to.  Types
+// without one, such as enums or types from other crates, may render as
+// anything, so nothing is checked against them.
+
+use proc_macro2::TokenStream as Tokens;
+use syn::{Ident, LitStr, Result, Type};
+
+use wrapped_type;
+
+// A tag found in the template, along with the line it was found on
+#[derive(Debug, PartialEq)]
+pub enum Tag {
+    Value(String),
+    Unescaped(String),
+    Open(String, bool),
+    Close(String),
+    Delimiters,
+    Other,
+}
+
+// Split a template into its tags.  Text between tags is of no interest.
+pub fn scan(source: &str) -> Vec<(usize, Tag)> {
+    let mut tags = Vec::new();
+    let mut rest = source;
+    let mut line = 1;
+
+    while let Some(open) = rest.find("{{") {
+        line += rest[..open].matches('\n').count();
+        let after = &rest[open + 2..];
+        let (inner, close) = if after.starts_with('{') {
+            match after.find("}}}") {
+                Some(close) => (&after[1..close], close + 3),
+                None => break,
+            }
+        } else {
+            match after.find("}}") {
+                Some(close) => (&after[..close], close + 2),
+                None => break,
+            }
+        };
+        let triple = after.starts_with('{');
+        let inner = inner.trim();
+        let tag = if triple {
+            Tag::Unescaped(inner.to_string())
+        } else if inner.is_empty() {
+            Tag::Other
+        } else {
+            let name = inner[1..].trim().to_string();
+            match &inner[0..1] {
+                "#" => Tag::Open(name, false),
+                "^" => Tag::Open(name, true),
+                "/" => Tag::Close(name),
+                "&" => Tag::Unescaped(name),
+                "=" => Tag::Delimiters,
+                "!" | ">" => Tag::Other,
+                _ => Tag::Value(inner.to_string()),
+            }
+        };
+        tags.push((line, tag));
+        line += after[..close].matches('\n').count();
+        rest = &after[close..];
+    }
+
+    tags
+}
+
+// The shape of a field's type, as far as it can be told from its syntax
+#[derive(Clone)]
+pub enum Shape {
+    Scalar,
+    Bool,
+    Option(Box<Shape>),
+    List(Box<Shape>),
+    Map,
+    // a type named by a path, which is a hash when it is a struct with
+    // named fields deriving `Mustache` and may be anything otherwise
+    Named(Box<Type>),
+    Unknown,
+}
+
+impl Shape {
+    pub fn of(ty: &Type, type_params: &[Ident]) -> Shape {
+        match *ty {
+            Type::Reference(ref reference) => Shape::of(&reference.elem, type_params),
+            Type::Paren(ref paren) => Shape::of(&paren.elem, type_params),
+            Type::Slice(ref slice) => Shape::List(Box::new(Shape::of(&slice.elem, type_params))),
+            Type::Array(ref array) => Shape::List(Box::new(Shape::of(&array.elem, type_params))),
+            Type::Path(ref path) if path.qself.is_none() => {
+                if let Some((wrapper, inner)) = wrapped_type(ty) {
+                    let inner = Shape::of(inner, type_params);
+                    return match &wrapper[..] {
+                        "Option" => Shape::Option(Box::new(inner)),
+                        "Vec" | "VecDeque" => Shape::List(Box::new(inner)),
+                        "Box" | "Rc" | "Arc" => inner,
+                        _ => Shape::Named(Box::new(ty.clone())),
+                    };
+                }
+                let ident = match path.path.segments.last() {
+                    Some(segment) => segment.ident.to_string(),
+                    None => return Shape::Unknown,
+                };
+                match &ident[..] {
+                    "String" | "str" | "Cow" | "char" | "i8" | "i16" | "i32" | "i64" |
+                    "i128" | "isize" | "u8" | "u16" | "u32" | "u64" | "u128" | "usize" |
+                    "f32" | "f64" => Shape::Scalar,
+                    "bool" => Shape::Bool,
+                    "HashMap" | "BTreeMap" | "HashBuilder" => Shape::Map,
+                    "VecBuilder" => Shape::List(Box::new(Shape::Unknown)),
+                    "Data" | "Json" => Shape::Unknown,
+                    _ if path.path.segments.len() == 1 &&
+                         type_params.iter().any(|param| *param == ident) => Shape::Unknown,
+                    _ => Shape::Named(Box::new(ty.clone())),
+                }
+            }
+            _ => Shape::Unknown,
+        }
+    }
+
+    // The `rustache::FieldShape` recorded for a field of this shape
+    pub fn field_shape(&self) -> Tokens {
+        match *self {
+            Shape::Scalar => quote!(::rustache::FieldShape::Scalar),
+            Shape::Bool => quote!(::rustache::FieldShape::Bool),
+            Shape::Option(_) => quote!(::rustache::FieldShape::Option),
+            Shape::List(_) => quote!(::rustache::FieldShape::List),
+            Shape::Map => quote!(::rustache::FieldShape::Hash),
+            Shape::Named(_) | Shape::Unknown => quote!(::rustache::FieldShape::Unknown),
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match *self {
+            Shape::Scalar => "a plain value",
+            Shape::Bool => "a bool",
+            Shape::Option(_) => "an Option",
+            Shape::List(_) => "a list",
+            Shape::Map => "a hash",
+            Shape::Named(_) | Shape::Unknown => "an unknown type",
+        }
+    }
+
+    // The context a section over a value of this shape pushes
+    fn scope(&self) -> Scope {
+        match *self {
+            Shape::Option(ref inner) | Shape::List(ref inner) => inner.scope(),
+            Shape::Named(ref ty) => Scope::Named((**ty).clone()),
+            Shape::Map | Shape::Unknown => Scope::Opaque,
+            Shape::Scalar | Shape::Bool => Scope::Transparent,
+        }
+    }
+}
+
+// A context on the checker's stack
+enum Scope {
+    // the struct being derived, whose fields are all known
+    Known(Vec<(String, Shape)>),
+    // a nested type, whose fields can only be checked by a const assertion
+    Named(Type),
+    // a value that may hold any key, so nothing under it can be checked
+    Opaque,
+    // a value that holds no keys, so lookups fall through it
+    Transparent,
+}
+
+// The outcome of resolving a name against the stack
+enum Resolved {
+    Found(Shape),
+    Deferred(Vec<Type>, String),
+    Unchecked,
+    Missing,
+}
+
+pub struct Checker<'a> {
+    path: &'a LitStr,
+    stack: Vec<Scope>,
+    sections: Vec<String>,
+    assertions: Vec<Tokens>,
+}
+
+impl<'a> Checker<'a> {
+    pub fn new(path: &'a LitStr, fields: Vec<(String, Shape)>) -> Checker<'a> {
+        Checker {
+            path,
+            stack: vec![Scope::Known(fields)],
+            sections: Vec::new(),
+            assertions: Vec::new(),
+        }
+    }
+
+    // Check every tag, returning the const assertions still to be made
+    // against nested types or the first error found
+    pub fn check(mut self, tags: &[(usize, Tag)]) -> Result<Vec<Tokens>> {
+        for &(line, ref tag) in tags.iter() {
+            match *tag {
+                Tag::Value(ref name) | Tag::Unescaped(ref name) => {
+                    self.check_name(line, name, false)?;
+                }
+                Tag::Open(ref name, inverted) => {
+                    let scope = self.check_name(line, name, true)?;
+                    self.stack.push(if inverted { Scope::Transparent } else { scope });
+                    self.sections.push(name.clone());
+                }
+                Tag::Close(ref name) => {
+                    match self.sections.pop() {
+                        Some(ref open) if open == name => {
+                            self.stack.pop();
+                        }
+                        Some(open) => {
+                            return Err(self.error(line,
+                                                  &format!("`{{{{/{}}}}}` closes `{{{{#{}}}}}`",
+                                                           name,
+                                                           open)))
+                        }
+                        None => {
+                            return Err(self.error(line,
+                                                  &format!("`{{{{/{}}}}}` was never opened", name)))
+                        }
+                    }
+                }
+                // the tags after a set delimiter tag can't be found
+                // without following it, so it is refused outright
+                Tag::Delimiters => {
+                    return Err(self.error(line,
+                                          "set delimiter tags are not supported in checked \
+                                           templates"))
+                }
+                Tag::Other => {}
+            }
+        }
+
+        if let Some(open) = self.sections.pop() {
+            let line = tags.last().map(|&(line, _)| line).unwrap_or(1);
+            return Err(self.error(line, &format!("`{{{{#{}}}}}` is never closed", open)));
+        }
+
+        Ok(self.assertions)
+    }
+
+    // Resolve a (possibly dotted) name used by a tag and check that it is of
+    // the right shape, returning the scope a section over it would push
+    fn check_name(&mut self, line: usize, name: &str, section: bool) -> Result<Scope> {
+        if name == "." {
+            return Ok(Scope::Opaque);
+        }
+
+        let mut parts = name.split('.');
+        let first = parts.next().unwrap_or("");
+        let mut resolved = self.resolve(first);
+
+        for part in parts {
+            resolved = match resolved {
+                Resolved::Found(shape) => self.descend(line, name, shape, part)?,
+                Resolved::Deferred(types, key) => {
+                    self.defer(line, &types, &key, true);
+                    Resolved::Unchecked
+                }
+                other => other,
+            };
+        }
+
+        match resolved {
+            Resolved::Found(shape) => {
+                if !self.usable(line, name, &shape, section) {
+                    let usage = if section { "a section" } else { "a value" };
+                    return Err(self.error(line,
+                                          &format!("`{}` is {} and cannot be used as {}",
+                                                   name,
+                                                   shape.describe(),
+                                                   usage)));
+                }
+                Ok(shape.scope())
+            }
+            Resolved::Deferred(types, key) => {
+                self.defer(line, &types, &key, section);
+                Ok(Scope::Opaque)
+            }
+            Resolved::Unchecked => Ok(Scope::Opaque),
+            Resolved::Missing => {
+                Err(self.error(line, &format!("no field named `{}`", name)))
+            }
+        }
+    }
+
+    // Whether a value of this shape can be used as a section, or as a value
+    // when `section` is false.  A named type can't be a value when it is a
+    // struct deriving `Mustache`, which is left to a const assertion.
+    fn usable(&mut self, line: usize, name: &str, shape: &Shape, section: bool) -> bool {
+        match *shape {
+            Shape::Scalar => !section,
+            Shape::List(_) | Shape::Map => section,
+            Shape::Option(ref inner) if !section => self.usable(line, name, inner, section),
+            Shape::Named(ref ty) if !section => {
+                let message = format!("{}:{}: `{}` is a struct and cannot be used as a value",
+                                      self.path.value(),
+                                      line,
+                                      name);
+                self.assertions.push(quote! {
+                    const _: () = {
+                        #[allow(unused_imports)]
+                        use ::rustache::NoFields;
+                        assert!(::rustache::FieldsOf::<#ty>::FIELDS.is_none(), #message);
+                    };
+                });
+                true
+            }
+            _ => true,
+        }
+    }
+
+    // Look a name up through the stack, innermost context first.  A field
+    // of the struct being derived is only known to be the one used when no
+    // nested type inside it may have the name first.
+    fn resolve(&self, key: &str) -> Resolved {
+        let mut nested = Vec::new();
+        for scope in self.stack.iter().rev() {
+            match *scope {
+                Scope::Known(ref fields) => {
+                    if let Some((_, shape)) = fields.iter().find(|(k, _)| k == key) {
+                        if !nested.is_empty() {
+                            return Resolved::Unchecked;
+                        }
+                        return Resolved::Found(shape.clone());
+                    }
+                }
+                Scope::Named(ref ty) => nested.push(ty.clone()),
+                Scope::Opaque => return Resolved::Unchecked,
+                Scope::Transparent => {}
+            }
+        }
+        if nested.is_empty() {
+            Resolved::Missing
+        } else {
+            Resolved::Deferred(nested, key.to_string())
+        }
+    }
+
+    // Resolve the next part of a dotted name against the previous part
+    fn descend(&mut self, line: usize, name: &str, shape: Shape, key: &str) -> Result<Resolved> {
+        match shape {
+            Shape::Option(inner) => self.descend(line, name, *inner, key),
+            Shape::Named(ty) => Ok(Resolved::Deferred(vec![*ty], key.to_string())),
+            // a number picks an item of a list
+            Shape::List(item) if !key.is_empty() && key.chars().all(|c| c.is_ascii_digit()) => {
+                Ok(Resolved::Found(*item))
+            }
+            Shape::Map | Shape::Unknown => Ok(Resolved::Unchecked),
+            other => {
+                Err(self.error(line,
+                               &format!("`{}` is {} and has no field `{}`",
+                                        name,
+                                        other.describe(),
+                                        key)))
+            }
+        }
+    }
+
+    // Record a const assertion that one of `types` has a field `key`
+    // usable in the given position
+    fn defer(&mut self, line: usize, types: &[Type], key: &str, section: bool) {
+        let usage = if section { "section" } else { "value" };
+        let message = format!("{}:{}: no {} field named `{}` in {}",
+                              self.path.value(),
+                              line,
+                              usage,
+                              key,
+                              types.iter()
+                                  .map(|ty| format!("`{}`", quote!(#ty)))
+                                  .collect::<Vec<_>>()
+                                  .join(" or "));
+        let checks = types.iter().map(|ty| {
+            quote! {
+                ::rustache::FieldShape::may_accept(::rustache::FieldsOf::<#ty>::FIELDS,
+                                                   #key,
+                                                   #section)
+            }
+        });
+        self.assertions.push(quote! {
+            const _: () = {
+                #[allow(unused_imports)]
+                use ::rustache::NoFields;
+                assert!(#(#checks)||*, #message);
+            };
+        });
+    }
+
+    fn error(&self, line: usize, message: &str) -> syn::Error {
+        syn::Error::new(self.path.span(),
+                        format!("{}:{}: {}", self.path.value(), line, message))
+    }
+}
+
+#[cfg(test)]
+mod template_tests {
+    use proc_macro2::Span;
+    use syn::{self, LitStr};
+
+    use template::{scan, Checker, Shape, Tag};
+
+    fn shape(ty: &str) -> Shape {
+        Shape::of(&syn::parse_str(ty).unwrap(), &[])
+    }
+
+    fn check(template: &str, fields: &[(&str, &str)]) -> Result<usize, String> {
+        let path = LitStr::new("hero.html", Span::call_site());
+        let fields = fields.iter().map(|&(key, ty)| (key.to_string(), shape(ty))).collect();
+        Checker::new(&path, fields)
+            .check(&scan(template))
+            .map(|assertions| assertions.len())
+            .map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn test_scan() {
+        let tags = scan("<h1>{{ name }}</h1>\n{{#cards}}\n{{{text}}}{{&html}}{{/cards}}{{! note }}");
+        assert_eq!(tags,
+                   vec![(1, Tag::Value("name".to_string())),
+                        (2, Tag::Open("cards".to_string(), false)),
+                        (3, Tag::Unescaped("text".to_string())),
+                        (3, Tag::Unescaped("html".to_string())),
+                        (3, Tag::Close("cards".to_string())),
+                        (3, Tag::Other)]);
+    }
+
+    #[test]
+    fn test_scan_unclosed_tag() {
+        assert_eq!(scan("{{#a}}{{b"), vec![(1, Tag::Open("a".to_string(), false))]);
+    }
+
+    #[test]
+    fn test_check_known_fields() {
+        assert_eq!(check("{{name}}{{#alive}}{{age}}{{/alive}}{{^title}}-{{/title}}{{title}}",
+                         &[("name", "String"),
+                           ("age", "u64"),
+                           ("alive", "bool"),
+                           ("title", "Option<&'static str>")]),
+                   Ok(0));
+    }
+
+    #[test]
+    fn test_check_missing_field() {
+        assert_eq!(check("<h1>\n{{nmae}}</h1>", &[("name", "String")]),
+                   Err("hero.html:2: no field named `nmae`".to_string()));
+    }
+
+    #[test]
+    fn test_check_list_as_value() {
+        assert_eq!(check("{{cards}}", &[("cards", "Vec<Card>")]),
+                   Err("hero.html:1: `cards` is a list and cannot be used as a value"
+                       .to_string()));
+    }
+
+    #[test]
+    fn test_check_scalar_as_section() {
+        assert_eq!(check("{{#name}}{{/name}}", &[("name", "String")]),
+                   Err("hero.html:1: `name` is a plain value and cannot be used as a section"
+                       .to_string()));
+    }
+
+    #[test]
+    fn test_check_dotted_into_scalar() {
+        assert_eq!(check("{{name.first}}", &[("name", "String")]),
+                   Err("hero.html:1: `name.first` is a plain value and has no field `first`"
+                       .to_string()));
+    }
+
+    #[test]
+    fn test_check_list_index() {
+        assert_eq!(check("{{cards.0.name}}{{#cards.1}}{{cost}}{{/cards.1}}{{tags.0}}",
+                         &[("cards", "Vec<Card>"), ("tags", "Vec<String>")]),
+                   Ok(2));
+        assert_eq!(check("{{cards.first}}", &[("cards", "Vec<Card>")]),
+                   Err("hero.html:1: `cards.first` is a list and has no field `first`"
+                       .to_string()));
+    }
+
+    #[test]
+    fn test_check_mismatched_sections() {
+        assert_eq!(check("{{#a}}{{#b}}{{/a}}{{/b}}", &[("a", "bool"), ("b", "bool")]),
+                   Err("hero.html:1: `{{/a}}` closes `{{#b}}`".to_string()));
+        assert_eq!(check("{{#a}}\n", &[("a", "bool")]),
+                   Err("hero.html:1: `{{#a}}` is never closed".to_string()));
+        assert_eq!(check("{{/a}}", &[("a", "bool")]),
+                   Err("hero.html:1: `{{/a}}` was never opened".to_string()));
+    }
+
+    #[test]
+    fn test_check_nested_struct_is_deferred() {
+        assert_eq!(check("{{#cards}}{{name}}{{cost}}{{/cards}}{{stats.hp}}",
+                         &[("cards", "Vec<Card>"), ("stats", "Stats")]),
+                   Ok(3));
+    }
+
+    #[test]
+    fn test_check_named_type_as_value_is_deferred() {
+        assert_eq!(check("{{status}}{{#status}}-{{/status}}{{rank}}",
+                         &[("status", "Status"), ("rank", "Option<Rank>")]),
+                   Ok(2));
+    }
+
+    #[test]
+    fn test_check_outer_field_inside_nested_struct() {
+        assert_eq!(check("{{#cards}}{{hero}}{{/cards}}",
+                         &[("cards", "Vec<Card>"), ("hero", "String")]),
+                   Ok(0));
+    }
+
+    #[test]
+    fn test_check_inner_struct_shadows_outer_field() {
+        assert_eq!(check("{{#cards}}{{#name}}-{{/name}}{{/cards}}",
+                         &[("cards", "Vec<Card>"), ("name", "String")]),
+                   Ok(0));
+        assert_eq!(check("{{#alive}}{{#name}}-{{/name}}{{/alive}}",
+                         &[("alive", "bool"), ("name", "String")]),
+                   Err("hero.html:1: `name` is a plain value and cannot be used as a section"
+                       .to_string()));
+    }
+
+    #[test]
+    fn test_check_set_delimiters() {
+        assert_eq!(check("{{name}}\n{{=<% %>=}}<% name %>", &[("name", "String")]),
+                   Err("hero.html:2: set delimiter tags are not supported in checked templates"
+                       .to_string()));
+    }
+
+    #[test]
+    fn test_check_map_is_unchecked() {
+        assert_eq!(check("{{#settings}}{{anything}}{{/settings}}{{settings.more}}",
+                         &[("settings", "HashMap<String, String>")]),
+                   Ok(0));
+    }
+}
@@ -1,14 +1,18 @@
-use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::convert::Into;
+use std::iter::FromIterator;
 
-use Data;
+use indexmap::IndexMap;
+
+use {ContextLambda, Data, DataLambda, Entry, LambdaContext, Lazy, Memo, SharedLambda, Stream};
 use Data::{Hash, Vector};
 
-/// `HashBuilder` is a helper type that constructs `Data` types in a `HashMap`
-#[derive(Debug)]
+/// `HashBuilder` is a helper type that constructs `Data` types in a map,
+/// which keeps its keys in the order they were first inserted
+#[derive(Clone, Debug)]
 pub struct HashBuilder<'a> {
     #[doc(hidden)]
-    pub data: HashMap<String, Data<'a>>,
+    pub data: IndexMap<String, Data<'a>>,
     #[doc(hidden)]
     pub partials_path: &'a str,
 }
@@ -17,7 +21,7 @@ impl<'a> HashBuilder<'a> {
     /// Create a new `HashBuilder` instance
     pub fn new() -> HashBuilder<'a> {
         HashBuilder {
-            data: HashMap::new(),
+            data: IndexMap::new(),
             partials_path: "",
         }
     }
@@ -61,7 +65,9 @@ impl<'a> HashBuilder<'a> {
         self
     }
 
-    /// Add a `Lambda` that accepts a String and returns a String to the `HashBuilder`
+    /// Add a `Lambda` that accepts a String and returns a String to the `HashBuilder`.
+    /// The lambda must be `Send`, so that the data holding it can still be
+    /// shared between threads.
     ///
     /// ```rust
     /// use rustache::HashBuilder;
@@ -69,13 +75,143 @@ impl<'a> HashBuilder<'a> {
     /// let data = HashBuilder::new()
     ///     .insert_lambda("lambda", &mut f);
     /// ```
+    ///
+    /// ```rust,compile_fail
+    /// use std::rc::Rc;
+    /// use rustache::HashBuilder;
+    /// let name = Rc::new("world".to_string());
+    /// let mut f = move |_| { name.to_string() };
+    /// let data = HashBuilder::new()
+    ///     .insert_lambda("lambda", &mut f);
+    /// ```
     pub fn insert_lambda<K: ToString>(self,
                                       key: K,
-                                      f: &'a mut FnMut(String) -> String)
+                                      f: &'a mut (dyn FnMut(String) -> String + Send))
                                       -> HashBuilder<'a> {
         self.insert(key, f)
     }
 
+    /// Add an owned `Lambda` that accepts a String and returns a String to
+    /// the `HashBuilder`.  Unlike `insert_lambda`, the builder keeps no
+    /// borrow, so it can be `'static` and shared between threads.
+    ///
+    /// ```rust
+    /// use rustache::HashBuilder;
+    /// let data: HashBuilder<'static> = HashBuilder::new()
+    ///     .insert_fn("lambda", |_| "world".to_string());
+    /// ```
+    pub fn insert_fn<K, F>(self, key: K, f: F) -> HashBuilder<'a>
+        where K: ToString,
+              F: Fn(String) -> String + Send + Sync + 'a
+    {
+        let f: SharedLambda<'a> = Arc::new(f);
+        self.insert(key, f)
+    }
+
+    /// Add a `Lambda` that is given the raw text of its section and a
+    /// `LambdaContext`, which renders text and looks up names with the data
+    /// around the lambda's tag.  What the lambda returns is written out
+    /// as it is.
+    ///
+    /// ```rust
+    /// use rustache::{HashBuilder, Render, VecBuilder};
+    /// use std::io::Cursor;
+    ///
+    /// let data = HashBuilder::new()
+    ///     .insert("users", VecBuilder::new()
+    ///         .push(HashBuilder::new().insert("id", 1))
+    ///         .push(HashBuilder::new().insert("id", 2)))
+    ///     .insert_context_lambda("link", |text, context| {
+    ///         let url = context.render(text).unwrap();
+    ///         format!("<a href=\"{0}\">{0}</a> ", url)
+    ///     });
+    ///
+    /// let mut out = Cursor::new(Vec::new());
+    /// data.render("{{#users}}{{#link}}/users/{{id}}{{/link}}{{/users}}", &mut out).unwrap();
+    ///
+    /// assert_eq!("<a href=\"/users/1\">/users/1</a> <a href=\"/users/2\">/users/2</a> ",
+    ///            String::from_utf8(out.into_inner()).unwrap());
+    /// ```
+    pub fn insert_context_lambda<K, F>(self, key: K, f: F) -> HashBuilder<'a>
+        where K: ToString,
+              F: Fn(&str, &mut LambdaContext) -> String + Send + Sync + 'a
+    {
+        let f: ContextLambda<'a> = Arc::new(f);
+        self.insert(key, f)
+    }
+
+    /// Add a `Lambda` that returns `Data`, which is called each time its
+    /// tag is rendered.  What it returns is used like any other data for
+    /// the tag: a list is iterated, a hash is looked into, and a section
+    /// only renders when the data is truthy.
+    ///
+    /// ```rust
+    /// use rustache::{HashBuilder, Render, VecBuilder};
+    /// use std::io::Cursor;
+    ///
+    /// let data = HashBuilder::new()
+    ///     .insert("page", 2)
+    ///     .insert_data_lambda("rows", |context| {
+    ///         let page = context.lookup("page")
+    ///             .and_then(|page| page.interpolate())
+    ///             .and_then(|page| page.parse::<usize>().ok())
+    ///             .unwrap_or(1);
+    ///         (page * 2 - 1..page * 2 + 1)
+    ///             .map(|id| HashBuilder::new().insert("id", id))
+    ///             .fold(VecBuilder::new(), VecBuilder::push)
+    ///     });
+    ///
+    /// let mut out = Cursor::new(Vec::new());
+    /// data.render("{{#rows}}{{id}} {{/rows}}", &mut out).unwrap();
+    ///
+    /// assert_eq!("3 4 ", String::from_utf8(out.into_inner()).unwrap());
+    /// ```
+    pub fn insert_data_lambda<K, F, V>(self, key: K, f: F) -> HashBuilder<'a>
+        where K: ToString,
+              F: Fn(&mut LambdaContext) -> V + Send + Sync + 'a,
+              V: Into<Data<'a>>
+    {
+        let f: DataLambda<'a> = Arc::new(move |context: &mut LambdaContext| f(context).into());
+        self.insert(key, f)
+    }
+
+    /// Add a value that is only computed when a template first uses it.
+    /// The result is kept for as long as the data is, not just for one
+    /// render, so the function runs at most once and every later render
+    /// reuses it.  Copies of the data share it, and comparing the data
+    /// computes it.
+    ///
+    /// ```rust
+    /// use rustache::HashBuilder;
+    /// let data = HashBuilder::new()
+    ///     .insert_lazy("card_count", || 30);
+    /// ```
+    pub fn insert_lazy<K, F, V>(self, key: K, f: F) -> HashBuilder<'a>
+        where K: ToString,
+              F: FnOnce() -> V + Send + 'a,
+              V: Into<Data<'a>>
+    {
+        self.insert(key, lazy(f))
+    }
+
+    /// Add a list whose items are taken from `iter` while a template
+    /// renders it, so they never all have to be in memory.  The items can
+    /// only be rendered once, and copies of the data share the same stream.
+    ///
+    /// ```rust
+    /// use rustache::HashBuilder;
+    /// let data = HashBuilder::new()
+    ///     .insert_stream("rows", (1..1000000).map(|id| HashBuilder::new().insert("id", id)));
+    /// ```
+    pub fn insert_stream<K, I, V>(self, key: K, iter: I) -> HashBuilder<'a>
+        where K: ToString,
+              I: IntoIterator<Item = V>,
+              I::IntoIter: Send + 'a,
+              V: Into<Data<'a>> + 'a
+    {
+        self.insert(key, stream(iter))
+    }
+
     /// Set a path to partials data
     pub fn set_partials_path(self, path: &'a str) -> HashBuilder<'a> {
         HashBuilder {
@@ -84,12 +220,112 @@ impl<'a> HashBuilder<'a> {
         }
     }
 
+    /// The value under `key`
+    ///
+    /// ```rust
+    /// use rustache::{Data, HashBuilder};
+    /// let data = HashBuilder::new()
+    ///     .insert("game", "Hearthstone");
+    ///
+    /// assert_eq!(Some(&Data::from("Hearthstone")), data.get("game"));
+    /// ```
+    pub fn get(&self, key: &str) -> Option<&Data<'a>> {
+        self.data.get(key)
+    }
+
+    /// A mutable reference to the value under `key`
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Data<'a>> {
+        self.data.get_mut(key)
+    }
+
+    /// The value at a dotted path, which can reach into nested hashes and,
+    /// by position, into vectors
+    ///
+    /// ```rust
+    /// use rustache::{Data, HashBuilder, VecBuilder};
+    /// let data = HashBuilder::new()
+    ///     .insert("heroes", VecBuilder::new()
+    ///         .push(HashBuilder::new().insert("name", "Anduin")));
+    ///
+    /// assert_eq!(Some(&Data::from("Anduin")), data.get_path("heroes.0.name"));
+    /// ```
+    pub fn get_path(&self, path: &str) -> Option<&Data<'a>> {
+        let (key, rest) = split_path(path);
+        self.get(key).and_then(|data| {
+            match rest {
+                Some(rest) => data.get_path(rest),
+                None => Some(data),
+            }
+        })
+    }
+
+    /// A mutable reference to the value at a dotted path, see `get_path`
+    pub fn get_path_mut(&mut self, path: &str) -> Option<&mut Data<'a>> {
+        let (key, rest) = split_path(path);
+        self.get_mut(key).and_then(|data| {
+            match rest {
+                Some(rest) => data.get_path_mut(rest),
+                None => Some(data),
+            }
+        })
+    }
+
+    /// Add a `Into<Data>` in place, returning the value it replaces
+    ///
+    /// ```rust
+    /// use rustache::HashBuilder;
+    /// let mut data = HashBuilder::new();
+    /// data.set("csrf_token", "4b1d");
+    /// ```
+    pub fn set<K, V>(&mut self, key: K, value: V) -> Option<Data<'a>>
+        where K: ToString,
+              V: Into<Data<'a>>
+    {
+        self.data.insert(key.to_string(), value.into())
+    }
+
+    /// Remove and return the value under `key`, keeping the order of the
+    /// keys after it
+    pub fn remove(&mut self, key: &str) -> Option<Data<'a>> {
+        self.data.shift_remove(key)
+    }
+
+    /// The entry for `key`, to add or change its value in place
+    ///
+    /// ```rust
+    /// use rustache::{Data, Entry, HashBuilder};
+    /// let mut data = HashBuilder::new();
+    /// data.entry("title").or_insert_with(|| Data::from("Untitled"));
+    /// if let Entry::Occupied(mut entry) = data.entry("title") {
+    ///     entry.insert(Data::from("Draft"));
+    /// }
+    /// ```
+    pub fn entry<K: ToString>(&mut self, key: K) -> Entry<'_, 'a> {
+        self.data.entry(key.to_string())
+    }
+
     /// Return the built `Data`
     fn build(self) -> Data<'a> {
         Hash(self.data)
     }
 }
 
+impl<'a, K: ToString, V: Into<Data<'a>>> FromIterator<(K, V)> for HashBuilder<'a> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> HashBuilder<'a> {
+        let mut builder = HashBuilder::new();
+        builder.extend(iter);
+        builder
+    }
+}
+
+impl<'a, K: ToString, V: Into<Data<'a>>> Extend<(K, V)> for HashBuilder<'a> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.set(key, value);
+        }
+    }
+}
+
 impl<'a> From<HashBuilder<'a>> for Data<'a> {
     fn from(v: HashBuilder<'a>) -> Data<'a> {
         v.build()
@@ -97,8 +333,9 @@ impl<'a> From<HashBuilder<'a>> for Data<'a> {
 }
 
 /// `VecBuilder` is a helper type that constructs `Data` types in a Vector
+#[derive(Clone)]
 pub struct VecBuilder<'a> {
-    data: Vec<Data<'a>>,
+    pub(crate) data: Vec<Data<'a>>,
 }
 
 impl<'a> VecBuilder<'a> {
@@ -151,25 +388,196 @@ impl<'a> VecBuilder<'a> {
     /// let data = VecBuilder::new()
     ///     .push_lambda(&mut f);
     /// ```
-    pub fn push_lambda(self, f: &'a mut FnMut(String) -> String) -> VecBuilder<'a> {
+    pub fn push_lambda(self, f: &'a mut (dyn FnMut(String) -> String + Send)) -> VecBuilder<'a> {
         self.push(f)
     }
 
+    /// Add an owned `Lambda` to the `VecBuilder`
+    ///
+    /// ```rust
+    /// use rustache::VecBuilder;
+    /// let data = VecBuilder::new()
+    ///     .push_fn(|_| "world".to_string());
+    /// ```
+    pub fn push_fn<F>(self, f: F) -> VecBuilder<'a>
+        where F: Fn(String) -> String + Send + Sync + 'a
+    {
+        let f: SharedLambda<'a> = Arc::new(f);
+        self.push(f)
+    }
+
+    /// Add a `Lambda` that is given a `LambdaContext` to render with, see
+    /// `HashBuilder::insert_context_lambda`
+    ///
+    /// ```rust
+    /// use rustache::VecBuilder;
+    /// let data = VecBuilder::new()
+    ///     .push_context_lambda(|text, context| context.render(text).unwrap());
+    /// ```
+    pub fn push_context_lambda<F>(self, f: F) -> VecBuilder<'a>
+        where F: Fn(&str, &mut LambdaContext) -> String + Send + Sync + 'a
+    {
+        let f: ContextLambda<'a> = Arc::new(f);
+        self.push(f)
+    }
+
+    /// Add a `Lambda` that returns `Data`, see
+    /// `HashBuilder::insert_data_lambda`
+    ///
+    /// ```rust
+    /// use rustache::VecBuilder;
+    /// let data = VecBuilder::new()
+    ///     .push_data_lambda(|_| "Mage");
+    /// ```
+    pub fn push_data_lambda<F, V>(self, f: F) -> VecBuilder<'a>
+        where F: Fn(&mut LambdaContext) -> V + Send + Sync + 'a,
+              V: Into<Data<'a>>
+    {
+        let f: DataLambda<'a> = Arc::new(move |context: &mut LambdaContext| f(context).into());
+        self.push(f)
+    }
+
+    /// Add a value that is only computed when a template first uses it.
+    /// The result is kept for as long as the data is, not just for one
+    /// render, so the function runs at most once and every later render
+    /// reuses it.  Copies of the data share it, and comparing the data
+    /// computes it.
+    ///
+    /// ```rust
+    /// use rustache::VecBuilder;
+    /// let data = VecBuilder::new()
+    ///     .push_lazy(|| "Mage");
+    /// ```
+    pub fn push_lazy<F, V>(self, f: F) -> VecBuilder<'a>
+        where F: FnOnce() -> V + Send + 'a,
+              V: Into<Data<'a>>
+    {
+        self.push(lazy(f))
+    }
+
+    /// Add a list whose items are taken from `iter` while a template
+    /// renders it.  The items can only be rendered once, and copies of the
+    /// data share the same stream.
+    ///
+    /// ```rust
+    /// use rustache::VecBuilder;
+    /// let data = VecBuilder::new()
+    ///     .push_stream(vec!["Mage", "Druid"]);
+    /// ```
+    pub fn push_stream<I, V>(self, iter: I) -> VecBuilder<'a>
+        where I: IntoIterator<Item = V>,
+              I::IntoIter: Send + 'a,
+              V: Into<Data<'a>> + 'a
+    {
+        self.push(stream(iter))
+    }
+
+    /// The value at `index`
+    pub fn get(&self, index: usize) -> Option<&Data<'a>> {
+        self.data.get(index)
+    }
+
+    /// A mutable reference to the value at `index`
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Data<'a>> {
+        self.data.get_mut(index)
+    }
+
+    /// The value at a dotted path starting with a position, such as
+    /// `0.name`
+    ///
+    /// ```rust
+    /// use rustache::{Data, HashBuilder, VecBuilder};
+    /// let data = VecBuilder::new()
+    ///     .push(HashBuilder::new().insert("name", "Anduin"));
+    ///
+    /// assert_eq!(Some(&Data::from("Anduin")), data.get_path("0.name"));
+    /// ```
+    pub fn get_path(&self, path: &str) -> Option<&Data<'a>> {
+        let (index, rest) = split_path(path);
+        index.parse().ok().and_then(|index| self.get(index)).and_then(|data| {
+            match rest {
+                Some(rest) => data.get_path(rest),
+                None => Some(data),
+            }
+        })
+    }
+
+    /// A mutable reference to the value at a dotted path, see `get_path`
+    pub fn get_path_mut(&mut self, path: &str) -> Option<&mut Data<'a>> {
+        let (index, rest) = split_path(path);
+        let data = match index.parse::<usize>() {
+            Ok(index) => self.data.get_mut(index),
+            Err(_) => None,
+        };
+        data.and_then(|data| {
+            match rest {
+                Some(rest) => data.get_path_mut(rest),
+                None => Some(data),
+            }
+        })
+    }
+
+    /// Remove and return the value at `index`, shifting the values after it
+    pub fn remove(&mut self, index: usize) -> Option<Data<'a>> {
+        if index < self.data.len() {
+            Some(self.data.remove(index))
+        } else {
+            None
+        }
+    }
+
     /// Return the built `Data`
     fn build(self) -> Data<'a> {
         Vector(self.data)
     }
 }
 
+impl<'a, V: Into<Data<'a>>> FromIterator<V> for VecBuilder<'a> {
+    fn from_iter<I: IntoIterator<Item = V>>(iter: I) -> VecBuilder<'a> {
+        VecBuilder { data: iter.into_iter().map(Into::into).collect() }
+    }
+}
+
+impl<'a, V: Into<Data<'a>>> Extend<V> for VecBuilder<'a> {
+    fn extend<I: IntoIterator<Item = V>>(&mut self, iter: I) {
+        self.data.extend(iter.into_iter().map(Into::into));
+    }
+}
+
 impl<'a> From<VecBuilder<'a>> for Data<'a> {
     fn from(v: VecBuilder<'a>) -> Data<'a> {
         v.build()
     }
 }
 
+// Split the first part off a dotted path
+fn split_path(path: &str) -> (&str, Option<&str>) {
+    let mut parts = path.splitn(2, '.');
+    (parts.next().unwrap_or(""), parts.next())
+}
+
+// Wrap `f` into `Data` that calls it on first use
+fn lazy<'a, F, V>(f: F) -> Data<'a>
+    where F: FnOnce() -> V + Send + 'a,
+          V: Into<Data<'a>>
+{
+    let f: Lazy<'a> = Box::new(move || f().into());
+    Data::Lazy(Arc::new(Memo::new(f)))
+}
+
+// Wrap `iter` into `Data` that takes its items as they are rendered
+fn stream<'a, I, V>(iter: I) -> Data<'a>
+    where I: IntoIterator<Item = V>,
+          I::IntoIter: Send + 'a,
+          V: Into<Data<'a>> + 'a
+{
+    let iter: Stream<'a> = Box::new(iter.into_iter().map(Into::into));
+    Data::Stream(Arc::new(Mutex::new(iter.peekable())))
+}
+
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use indexmap::IndexMap;
 
     use {HashBuilder, VecBuilder};
     use Data;
@@ -177,7 +585,7 @@ mod tests {
 
     #[test]
     fn test_new_builders() {
-        assert_eq!(HashBuilder::new().build(), Hash(HashMap::new()));
+        assert_eq!(HashBuilder::new().build(), Hash(IndexMap::new()));
         assert_eq!(VecBuilder::new().build(), Vector(Vec::new()));
     }
 
@@ -191,16 +599,16 @@ mod tests {
     fn test_builders() {
         let test_string = String::from("Conan the Sorcerian");
 
-        let mut hearthstone = HashMap::new();
+        let mut hearthstone = IndexMap::new();
         hearthstone.insert("name".to_string(),
                            Data::String("Hearthstone: Heroes of Warcraft".to_string()));
         hearthstone.insert("release_date".to_string(),
                            Data::String("December, 2014".to_string()));
 
-        let mut hash1 = HashMap::new();
+        let mut hash1 = IndexMap::new();
         hash1.insert("first_name".to_string(), Data::String("Anduin".to_string()));
         hash1.insert("last_name".to_string(), Data::String("Wrynn".to_string()));
-        hash1.insert("age".to_string(), Integer(21i32));
+        hash1.insert("age".to_string(), Integer(21i64));
         hash1.insert("weight".to_string(), Float(120.16f64));
         hash1.insert("class".to_string(), Data::String("Priest".to_string()));
         hash1.insert("died".to_string(), Bool(false));
@@ -276,7 +684,6 @@ mod tests {
             Vector(m) => {
                 match m[0] {
                     Lambda(ref f) => {
-                        let f = &mut *f.borrow_mut();
                         assert_eq!((*f)("double: ".to_string()), "double: 20".to_string());
                         assert_eq!((*f)("double: ".to_string()), "double: 40".to_string());
                         assert_eq!((*f)("double: ".to_string()), "double: 80".to_string());
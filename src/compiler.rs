@@ -18,12 +18,20 @@ pub enum Token<'a> {
     CTag(&'a str, &'a str), // (name, tag, whitespace)
     Raw(&'a str, &'a str), // (name, tag)
     Partial(&'a str, &'a str), // (name, tag)
+    Delimiters(&'a str, &'a str), // (otag, ctag)
     Comment,
 }
 
 // Entry point to the template compiler. It compiles a token list of
 // all applicable tags within a template to send to the parser.
 pub fn create_tokens(contents: &str) -> Vec<Token> {
+    create_tokens_with(contents, "{{", "}}")
+}
+
+// Compiles a template whose tags start out with the given delimiters,
+// such as the text a section lambda returns, which is compiled with the
+// delimiters of its section.
+pub fn create_tokens_with<'a>(contents: &'a str, otag: &str, ctag: &str) -> Vec<Token<'a>> {
     let mut tokens: Vec<Token> = Vec::new();
 
     // Close position and length are used to catch trailing characters afer last
@@ -31,13 +39,10 @@ pub fn create_tokens(contents: &str) -> Vec<Token> {
     let mut close_pos = 0;
     let len = contents.len();
 
-    // (text)(whitespace)( (tag) )(whitespace)
-    let re =
-        Regex::new(r"(?s)(.*?)([ \t\r\n]*)(\{\{(\{?\S?\s*?[\w\.\s]*.*?\s*?\}?)\}\})([ \t\r\n]*)")
-            .unwrap();
+    let mut re = tag_regex(otag, ctag);
 
-    // Grab all captures and process
-    for cap in re.captures_iter(contents) {
+    // Grab each capture and process, starting after the last one
+    while let Some(cap) = re.captures(&contents[close_pos..]) {
         // Establish groups for tag capture, preventing lookup for each call
         let preceding_text = cap.at(1).unwrap_or("");
         let preceding_whitespace = cap.at(2).unwrap_or("");
@@ -58,9 +63,16 @@ pub fn create_tokens(contents: &str) -> Vec<Token> {
             tokens.push(Text(preceding_whitespace));
         }
 
-        // Advance last closing position and add captured token
-        close_pos = c;
-        add_token(inner, outer, &mut tokens);
+        // Advance last closing position and add captured token.  A set
+        // delimiter tag changes how the tags after it are found.
+        close_pos += c;
+        match parse_delimiters(inner) {
+            Some((open, close)) => {
+                tokens.push(Delimiters(open, close));
+                re = tag_regex(open, close);
+            }
+            None => add_token(inner, outer, &mut tokens),
+        }
 
         // Catch trailing whitespace
         if !trailing_whitespace.is_empty() {
@@ -77,6 +89,28 @@ pub fn create_tokens(contents: &str) -> Vec<Token> {
     tokens
 }
 
+// (text)(whitespace)( (tag) )(whitespace)
+fn tag_regex(otag: &str, ctag: &str) -> Regex {
+    let re = format!(r"(?s)(.*?)([ \t\r\n]*)({}(\{{?\S?\s*?[\w\.\s]*.*?\s*?\}}?){})([ \t\r\n]*)",
+                     self::regex::quote(otag),
+                     self::regex::quote(ctag));
+    Regex::new(&re).unwrap()
+}
+
+// The new delimiters of a set delimiter tag, such as {{=<% %>=}}
+fn parse_delimiters(inner: &str) -> Option<(&str, &str)> {
+    let inner = inner.trim();
+    if inner.len() < 2 || !inner.starts_with('=') || !inner.ends_with('=') {
+        return None;
+    }
+
+    let mut parts = inner[1..inner.len() - 1].split_whitespace();
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(open), Some(close), None) => Some((open, close)),
+        _ => None,
+    }
+}
+
 // Simple method for categorizing and adding appropriate token
 fn add_token<'a>(inner: &'a str, outer: &'a str, tokens: &mut Vec<Token<'a>>) {
     match &inner[0..1] {
@@ -94,7 +128,7 @@ fn add_token<'a>(inner: &'a str, outer: &'a str, tokens: &mut Vec<Token<'a>>) {
 #[cfg(test)]
 mod compiler_tests {
     use compiler;
-    use compiler::Token::{Text, Variable, OTag, CTag, Raw, Partial, Comment};
+    use compiler::Token::{Text, Variable, OTag, CTag, Raw, Partial, Delimiters, Comment};
 
     #[test]
     fn test_one_char() {
@@ -286,4 +320,39 @@ mod compiler_tests {
 
         assert_eq!(expected, tokens);
     }
+
+    #[test]
+    fn test_set_delimiters() {
+        let contents = "{{=<% %>=}}<% name %> {{ name }} <%={{ }}=%>{{ name }}";
+        let tokens = compiler::create_tokens(contents);
+        let expected = vec![Delimiters("<%", "%>"),
+                            Variable("name", "<% name %>"),
+                            Text(" "),
+                            Text("{{ name }}"),
+                            Text(" "),
+                            Delimiters("{{", "}}"),
+                            Variable("name", "{{ name }}")];
+
+        assert_eq!(expected, tokens);
+    }
+
+    #[test]
+    fn test_create_tokens_with_delimiters() {
+        let contents = "|#lambda|{{ planet }}|/lambda|";
+        let tokens = compiler::create_tokens_with(contents, "|", "|");
+        let expected = vec![OTag("lambda", false, "|#lambda|"),
+                            Text("{{ planet }}"),
+                            CTag("lambda", "|/lambda|")];
+
+        assert_eq!(expected, tokens);
+    }
+
+    #[test]
+    fn test_bad_set_delimiters() {
+        let contents = "{{= | =}}";
+        let tokens = compiler::create_tokens(contents);
+        let expected = vec![Variable("= | =", "{{= | =}}")];
+
+        assert_eq!(expected, tokens);
+    }
 }
@@ -0,0 +1,587 @@
+// A context is any value the template can render from.  The template walks
+// its data only through this trait, so sources such as `serde_json::Value`
+// can be rendered in place instead of first being copied into a
+// `HashBuilder`.
+
+use std::borrow::Cow;
+use std::sync::MutexGuard;
+use std::iter::{self, Peekable};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::BuildHasher;
+
+use indexmap::IndexMap;
+
+#[cfg(feature = "serde_json")]
+use serde_json;
+
+use {Data, HashBuilder, Stream};
+use template::LambdaContext;
+use self::sealed::{Internal, Sealed};
+
+/// The items of a list, see `Context::items`
+pub type Items<'s> = Box<dyn Iterator<Item = Box<dyn Context + 's>> + 's>;
+
+/// The named fields of a value with their values, see `Context::entries`
+pub type Entries<'s> = Vec<(&'s str, &'s dyn Context)>;
+
+/// A value that templates can be rendered from
+///
+/// The template only reaches into its data through this trait, so any
+/// type implementing it can be rendered in place, without first being
+/// copied into a `HashBuilder`.  Every method has a default, so a type
+/// only implements what it supports: a row type would implement `lookup`,
+/// a collection `items`, and a plain value `interpolate`.
+///
+/// ```rust
+/// use rustache::{Context, Render};
+/// use std::io::Cursor;
+///
+/// struct User {
+///     name: String,
+///     admin: bool,
+/// }
+///
+/// impl Context for User {
+///     fn lookup(&self, key: &str) -> Option<&dyn Context> {
+///         match key {
+///             "name" => Some(&self.name),
+///             "admin" => Some(&self.admin),
+///             _ => None,
+///         }
+///     }
+/// }
+///
+/// let user = User { name: "Anduin".to_string(), admin: true };
+/// let mut out = Cursor::new(Vec::new());
+/// (&user as &dyn Context).render("{{ name }}{{#admin}} (admin){{/admin}}", &mut out).unwrap();
+///
+/// assert_eq!("Anduin (admin)", String::from_utf8(out.into_inner()).unwrap());
+/// ```
+pub trait Context {
+    /// Look up the value stored under `key`, when this value has named
+    /// fields
+    fn lookup(&self, _key: &str) -> Option<&dyn Context> {
+        None
+    }
+
+    /// Iterate over the items of this value, when it is a list.  A section
+    /// over a list renders once for each item.  Items are boxed so that a
+    /// list can hand out values it produces on the fly as well as values
+    /// it holds.
+    fn items(&self) -> Option<Items<'_>> {
+        None
+    }
+
+    /// The item at `index`, when this value is a list that holds its items.
+    /// A number in a dotted name picks an item this way, as in
+    /// `{{items.0.name}}`.
+    fn item(&self, _index: usize) -> Option<&dyn Context> {
+        None
+    }
+
+    /// List the named fields of this value, when it has fields that can be
+    /// listed, always in the same order.  With hash iteration enabled, a
+    /// section over `name.@entries` renders once for each entry, see
+    /// `Renderer::enable_hash_iteration`.
+    fn entries(&self) -> Option<Entries<'_>> {
+        None
+    }
+
+    /// Whether a section over this value renders its children, and an
+    /// inverted section doesn't
+    fn is_truthy(&self) -> bool {
+        true
+    }
+
+    /// The text written out for this value by an interpolation tag, when it
+    /// is a plain value
+    fn interpolate(&self) -> Option<Cow<'_, str>> {
+        None
+    }
+
+    /// Whether this value is a number, so that truthiness rules can tell
+    /// `0` from the text `"0"`, see `Truthiness::JavaScript`
+    fn is_number(&self) -> bool {
+        false
+    }
+
+    // the lambda or the data around this value, see `sealed::Internal`
+    #[doc(hidden)]
+    fn internal(&self, _sealed: Sealed) -> Option<&dyn Internal> {
+        None
+    }
+}
+
+// What only the template may do with a value: call the lambda it holds or
+// step out to the data around it.  Other crates can't name these types, so
+// they can neither call `Context::internal` nor override it.
+pub(crate) mod sealed {
+    use context::Context;
+    use template::LambdaContext;
+
+    #[derive(Clone, Copy)]
+    pub struct Sealed;
+
+    pub trait Internal {
+        fn is_lambda(&self) -> bool {
+            false
+        }
+
+        // calls the lambda held by this value with the given text
+        fn call_lambda(&self, _text: String) -> Option<String> {
+            None
+        }
+
+        // calls the context lambda held by this value, see `LambdaContext`
+        fn call_context_lambda(&self, _text: &str, _context: &mut LambdaContext) -> Option<String> {
+            None
+        }
+
+        // calls the data lambda held by this value, returning the data to
+        // use in its place
+        fn call_data_lambda(&self, _context: &mut LambdaContext) -> Option<Box<dyn Context + '_>> {
+            None
+        }
+
+        // the data around this value, when the template rendered it inside
+        // other data, such as an item of a list
+        fn parent_context(&self) -> Option<&dyn Context> {
+            None
+        }
+    }
+}
+
+// the template's way to the sealed methods of any context
+impl<'c> dyn Context + 'c {
+    pub(crate) fn is_lambda(&self) -> bool {
+        self.internal(Sealed).is_some_and(|data| data.is_lambda())
+    }
+
+    pub(crate) fn call_lambda(&self, text: String) -> Option<String> {
+        self.internal(Sealed).and_then(|data| data.call_lambda(text))
+    }
+
+    pub(crate) fn call_context_lambda(&self, text: &str, context: &mut LambdaContext) -> Option<String> {
+        self.internal(Sealed).and_then(|data| data.call_context_lambda(text, context))
+    }
+
+    pub(crate) fn call_data_lambda(&self, context: &mut LambdaContext) -> Option<Box<dyn Context + '_>> {
+        self.internal(Sealed).and_then(|data| data.call_data_lambda(context))
+    }
+
+    pub(crate) fn parent_context(&self) -> Option<&dyn Context> {
+        self.internal(Sealed).and_then(|data| data.parent_context())
+    }
+}
+
+// hashes are whatever is left once plain values, lists and lambdas
+// are ruled out
+pub fn is_hash(data: &dyn Context) -> bool {
+    !data.is_lambda() && data.interpolate().is_none() && data.items().is_none()
+}
+
+fn boxed<'s, T: Context>(item: &'s T) -> Box<dyn Context + 's> {
+    Box::new(item)
+}
+
+// Hands out the items of a stream as they are produced
+struct StreamItems<'s, 'a: 's>(MutexGuard<'s, Peekable<Stream<'a>>>);
+
+impl<'s, 'a> Iterator for StreamItems<'s, 'a> {
+    type Item = Box<dyn Context + 's>;
+
+    fn next(&mut self) -> Option<Box<dyn Context + 's>> {
+        self.0.next().map(|data| Box::new(data) as Box<dyn Context + 's>)
+    }
+}
+
+impl<'a> Data<'a> {
+    // the data behind a lazy value, which is computed the first time
+    // it's needed and kept for every use after that
+    fn resolved(&self) -> &Data<'a> {
+        match *self {
+            Data::Lazy(ref cell) => cell.force().resolved(),
+            _ => self,
+        }
+    }
+}
+
+impl<'a> Context for Data<'a> {
+    fn lookup(&self, key: &str) -> Option<&dyn Context> {
+        match *self.resolved() {
+            Data::Hash(ref hash) => hash.get(key).map(|data| data as &dyn Context),
+            _ => None,
+        }
+    }
+
+    fn items(&self) -> Option<Items<'_>> {
+        match *self.resolved() {
+            Data::Vector(ref list) => Some(Box::new(list.iter().map(boxed))),
+            Data::Stream(ref stream) => {
+                // a stream that is already being rendered further up has
+                // nothing left to give
+                match stream.try_lock() {
+                    Ok(stream) => Some(Box::new(StreamItems(stream))),
+                    Err(_) => Some(Box::new(iter::empty())),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn item(&self, index: usize) -> Option<&dyn Context> {
+        match *self.resolved() {
+            Data::Vector(ref list) => list.get(index).map(|data| data as &dyn Context),
+            _ => None,
+        }
+    }
+
+    fn entries(&self) -> Option<Entries<'_>> {
+        match *self.resolved() {
+            Data::Hash(ref hash) => hash.entries(),
+            _ => None,
+        }
+    }
+
+    // data is only false when it is a false bool, an empty vector or null
+    fn is_truthy(&self) -> bool {
+        match *self.resolved() {
+            Data::Bool(value) => value,
+            Data::Null => false,
+            Data::Vector(ref list) => !list.is_empty(),
+            Data::Stream(ref stream) => {
+                match stream.try_lock() {
+                    Ok(mut stream) => stream.peek().is_some(),
+                    Err(_) => false,
+                }
+            }
+            _ => true,
+        }
+    }
+
+    fn interpolate(&self) -> Option<Cow<'_, str>> {
+        match *self.resolved() {
+            Data::String(ref val) => Some(Cow::Borrowed(&val[..])),
+            Data::Bool(val) => Some(Cow::Borrowed(if val { "true" } else { "false" })),
+            Data::Integer(val) => Some(Cow::Owned(val.to_string())),
+            Data::Unsigned(val) => Some(Cow::Owned(val.to_string())),
+            Data::Float(val) => Some(Cow::Owned(val.to_string())),
+            _ => None,
+        }
+    }
+
+    fn is_number(&self) -> bool {
+        matches!(*self.resolved(), Data::Integer(_) | Data::Unsigned(_) | Data::Float(_))
+    }
+
+    fn internal(&self, _sealed: Sealed) -> Option<&dyn Internal> {
+        Some(self)
+    }
+}
+
+impl<'a> Internal for Data<'a> {
+    fn is_lambda(&self) -> bool {
+        matches!(*self.resolved(),
+                 Data::Lambda(_) | Data::ContextLambda(_) | Data::DataLambda(_))
+    }
+
+    fn call_lambda(&self, text: String) -> Option<String> {
+        match *self.resolved() {
+            Data::Lambda(ref f) => Some(f(text)),
+            _ => None,
+        }
+    }
+
+    fn call_context_lambda(&self, text: &str, context: &mut LambdaContext) -> Option<String> {
+        match *self.resolved() {
+            Data::ContextLambda(ref f) => Some(f(text, context)),
+            _ => None,
+        }
+    }
+
+    fn call_data_lambda(&self, context: &mut LambdaContext) -> Option<Box<dyn Context + '_>> {
+        match *self.resolved() {
+            Data::DataLambda(ref f) => Some(Box::new(f(context))),
+            _ => None,
+        }
+    }
+}
+
+impl<T: Context + ?Sized> Context for &T {
+    fn lookup(&self, key: &str) -> Option<&dyn Context> {
+        (**self).lookup(key)
+    }
+
+    fn items(&self) -> Option<Items<'_>> {
+        (**self).items()
+    }
+
+    fn item(&self, index: usize) -> Option<&dyn Context> {
+        (**self).item(index)
+    }
+
+    fn entries(&self) -> Option<Entries<'_>> {
+        (**self).entries()
+    }
+
+    fn is_truthy(&self) -> bool {
+        (**self).is_truthy()
+    }
+
+    fn interpolate(&self) -> Option<Cow<'_, str>> {
+        (**self).interpolate()
+    }
+
+    fn is_number(&self) -> bool {
+        (**self).is_number()
+    }
+
+    fn internal(&self, sealed: Sealed) -> Option<&dyn Internal> {
+        (**self).internal(sealed)
+    }
+}
+
+impl<T: Context + ?Sized> Context for Box<T> {
+    fn lookup(&self, key: &str) -> Option<&dyn Context> {
+        (**self).lookup(key)
+    }
+
+    fn items(&self) -> Option<Items<'_>> {
+        (**self).items()
+    }
+
+    fn item(&self, index: usize) -> Option<&dyn Context> {
+        (**self).item(index)
+    }
+
+    fn entries(&self) -> Option<Entries<'_>> {
+        (**self).entries()
+    }
+
+    fn is_truthy(&self) -> bool {
+        (**self).is_truthy()
+    }
+
+    fn interpolate(&self) -> Option<Cow<'_, str>> {
+        (**self).interpolate()
+    }
+
+    fn is_number(&self) -> bool {
+        (**self).is_number()
+    }
+
+    fn internal(&self, sealed: Sealed) -> Option<&dyn Internal> {
+        (**self).internal(sealed)
+    }
+}
+
+impl Context for str {
+    fn interpolate(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self))
+    }
+}
+
+impl Context for String {
+    fn interpolate(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(&self[..]))
+    }
+}
+
+impl Context for bool {
+    fn is_truthy(&self) -> bool {
+        *self
+    }
+
+    fn interpolate(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(if *self { "true" } else { "false" }))
+    }
+}
+
+macro_rules! number_context {
+    ($($ty:ty),*) => {
+        $(
+            impl Context for $ty {
+                fn interpolate(&self) -> Option<Cow<'_, str>> {
+                    Some(Cow::Owned(self.to_string()))
+                }
+
+                fn is_number(&self) -> bool {
+                    true
+                }
+            }
+        )*
+    }
+}
+
+number_context!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64);
+
+// `None` is rendered like null
+impl<T: Context> Context for Option<T> {
+    fn lookup(&self, key: &str) -> Option<&dyn Context> {
+        self.as_ref().and_then(|value| value.lookup(key))
+    }
+
+    fn items(&self) -> Option<Items<'_>> {
+        self.as_ref().and_then(|value| value.items())
+    }
+
+    fn item(&self, index: usize) -> Option<&dyn Context> {
+        self.as_ref().and_then(|value| value.item(index))
+    }
+
+    fn entries(&self) -> Option<Entries<'_>> {
+        self.as_ref().and_then(|value| value.entries())
+    }
+
+    fn is_truthy(&self) -> bool {
+        self.as_ref().is_some_and(|value| value.is_truthy())
+    }
+
+    fn interpolate(&self) -> Option<Cow<'_, str>> {
+        self.as_ref().and_then(|value| value.interpolate())
+    }
+
+    fn is_number(&self) -> bool {
+        self.as_ref().is_some_and(|value| value.is_number())
+    }
+
+    fn internal(&self, sealed: Sealed) -> Option<&dyn Internal> {
+        self.as_ref().and_then(|value| value.internal(sealed))
+    }
+}
+
+impl<T: Context> Context for [T] {
+    fn items(&self) -> Option<Items<'_>> {
+        Some(Box::new(<[T]>::iter(self).map(boxed)))
+    }
+
+    fn item(&self, index: usize) -> Option<&dyn Context> {
+        self.get(index).map(|value| value as &dyn Context)
+    }
+
+    fn is_truthy(&self) -> bool {
+        !self.is_empty()
+    }
+}
+
+impl<T: Context> Context for Vec<T> {
+    fn items(&self) -> Option<Items<'_>> {
+        Context::items(&self[..])
+    }
+
+    fn item(&self, index: usize) -> Option<&dyn Context> {
+        Context::item(&self[..], index)
+    }
+
+    fn is_truthy(&self) -> bool {
+        !self.is_empty()
+    }
+}
+
+impl<T: Context, S: BuildHasher> Context for HashMap<String, T, S> {
+    fn lookup(&self, key: &str) -> Option<&dyn Context> {
+        HashMap::get(self, key).map(|value| value as &dyn Context)
+    }
+
+    fn entries(&self) -> Option<Entries<'_>> {
+        Some(sorted_entries(self.iter()))
+    }
+}
+
+// kept in the order the keys were inserted
+impl<T: Context, S: BuildHasher> Context for IndexMap<String, T, S> {
+    fn lookup(&self, key: &str) -> Option<&dyn Context> {
+        IndexMap::get(self, key).map(|value| value as &dyn Context)
+    }
+
+    fn entries(&self) -> Option<Entries<'_>> {
+        Some(self.iter().map(|(key, value)| (&key[..], value as &dyn Context)).collect())
+    }
+}
+
+impl<T: Context> Context for BTreeMap<String, T> {
+    fn lookup(&self, key: &str) -> Option<&dyn Context> {
+        BTreeMap::get(self, key).map(|value| value as &dyn Context)
+    }
+
+    fn entries(&self) -> Option<Entries<'_>> {
+        Some(self.iter().map(|(key, value)| (&key[..], value as &dyn Context)).collect())
+    }
+}
+
+impl<'a> Context for HashBuilder<'a> {
+    fn lookup(&self, key: &str) -> Option<&dyn Context> {
+        self.data.lookup(key)
+    }
+
+    fn entries(&self) -> Option<Entries<'_>> {
+        self.data.entries()
+    }
+}
+
+// hash maps have no order of their own, so their entries are sorted by key
+fn sorted_entries<'s, T: Context + 's, I>(iter: I) -> Entries<'s>
+    where I: Iterator<Item = (&'s String, &'s T)>
+{
+    let mut entries: Entries = iter.map(|(key, value)| (&key[..], value as &dyn Context)).collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    entries
+}
+
+// JSON values render like the data `parse_json` builds from them
+#[cfg(feature = "serde_json")]
+impl Context for serde_json::Value {
+    fn lookup(&self, key: &str) -> Option<&dyn Context> {
+        match *self {
+            serde_json::Value::Object(ref map) => map.get(key).map(|value| value as &dyn Context),
+            _ => None,
+        }
+    }
+
+    fn items(&self) -> Option<Items<'_>> {
+        match *self {
+            serde_json::Value::Array(ref list) => {
+                Some(Box::new(list.iter().map(boxed)))
+            }
+            _ => None,
+        }
+    }
+
+    fn item(&self, index: usize) -> Option<&dyn Context> {
+        match *self {
+            serde_json::Value::Array(ref list) => list.get(index).map(|value| value as &dyn Context),
+            _ => None,
+        }
+    }
+
+    fn entries(&self) -> Option<Entries<'_>> {
+        match *self {
+            serde_json::Value::Object(ref map) => {
+                Some(map.iter().map(|(key, value)| (&key[..], value as &dyn Context)).collect())
+            }
+            _ => None,
+        }
+    }
+
+    fn is_truthy(&self) -> bool {
+        match *self {
+            serde_json::Value::Null => false,
+            serde_json::Value::Bool(value) => value,
+            serde_json::Value::Array(ref list) => !list.is_empty(),
+            _ => true,
+        }
+    }
+
+    fn interpolate(&self) -> Option<Cow<'_, str>> {
+        match *self {
+            serde_json::Value::String(ref val) => Some(Cow::Borrowed(&val[..])),
+            serde_json::Value::Bool(val) => Some(Cow::Borrowed(if val { "true" } else { "false" })),
+            serde_json::Value::Number(ref val) => Some(Cow::Owned(val.to_string())),
+            _ => None,
+        }
+    }
+
+    fn is_number(&self) -> bool {
+        serde_json::Value::is_number(self)
+    }
+}
@@ -28,5 +28,10 @@ error_chain! {
             description("unexpected node type")
             display("{}", t)
         }
+
+        UnknownFilter(name: String) {
+            description("unknown filter")
+            display("unknown filter: {}", name)
+        }
     }
 }
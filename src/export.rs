@@ -0,0 +1,254 @@
+// Writing data back out, as JSON or as a tree for reading.  Lambdas and
+// streams have no value to show, so they are written as placeholders.
+// Lazy data is computed, since its value is what a template would get.
+// Hashes keep their order, except in `Json`, whose objects sort their keys.
+
+use std::fmt::Write;
+
+use rustc_serialize::json::{Json, ToJson};
+#[cfg(feature = "serde")]
+use serde::ser::{Serialize, Serializer, SerializeMap};
+
+use {Data, Hash};
+use build::{HashBuilder, VecBuilder};
+
+const LAMBDA_PLACEHOLDER: &str = "<lambda>";
+// reading a stream would use it up
+const STREAM_PLACEHOLDER: &str = "<stream>";
+
+impl<'a> ToJson for Data<'a> {
+    fn to_json(&self) -> Json {
+        match *self {
+            Data::String(ref val) => Json::String(val.clone()),
+            Data::Bool(val) => Json::Boolean(val),
+            Data::Integer(val) => Json::I64(val),
+            Data::Unsigned(val) => Json::U64(val),
+            Data::Float(val) => Json::F64(val),
+            Data::Vector(ref list) => Json::Array(list.iter().map(ToJson::to_json).collect()),
+            Data::Hash(ref hash) => {
+                Json::Object(hash.iter().map(|(k, v)| (k.clone(), v.to_json())).collect())
+            }
+            Data::Lambda(_) |
+            Data::ContextLambda(_) |
+            Data::DataLambda(_) => Json::String(LAMBDA_PLACEHOLDER.to_string()),
+            Data::Lazy(ref cell) => cell.force().to_json(),
+            Data::Stream(_) => Json::String(STREAM_PLACEHOLDER.to_string()),
+            Data::Null => Json::Null,
+        }
+    }
+}
+
+impl<'a> ToJson for HashBuilder<'a> {
+    fn to_json(&self) -> Json {
+        Json::Object(self.data.iter().map(|(k, v)| (k.clone(), v.to_json())).collect())
+    }
+}
+
+impl<'a> ToJson for VecBuilder<'a> {
+    fn to_json(&self) -> Json {
+        self.data.to_json()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'a> Serialize for Data<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            Data::String(ref val) => serializer.serialize_str(val),
+            Data::Bool(val) => serializer.serialize_bool(val),
+            Data::Integer(val) => serializer.serialize_i64(val),
+            Data::Unsigned(val) => serializer.serialize_u64(val),
+            Data::Float(val) => serializer.serialize_f64(val),
+            Data::Vector(ref list) => serializer.collect_seq(list),
+            Data::Hash(ref hash) => serialize_hash(hash, serializer),
+            Data::Lambda(_) |
+            Data::ContextLambda(_) |
+            Data::DataLambda(_) => serializer.serialize_str(LAMBDA_PLACEHOLDER),
+            Data::Lazy(ref cell) => cell.force().serialize(serializer),
+            Data::Stream(_) => serializer.serialize_str(STREAM_PLACEHOLDER),
+            Data::Null => serializer.serialize_unit(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'a> Serialize for HashBuilder<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_hash(&self.data, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'a> Serialize for VecBuilder<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(&self.data)
+    }
+}
+
+#[cfg(feature = "serde")]
+fn serialize_hash<S: Serializer>(hash: &Hash, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut map = try!(serializer.serialize_map(Some(hash.len())));
+    for (key, value) in hash {
+        try!(map.serialize_entry(key, value));
+    }
+    map.end()
+}
+
+impl<'a> Data<'a> {
+    /// An indented tree of the data, one value per line, with the keys of
+    /// hashes quoted like strings and in the order they were inserted
+    ///
+    /// ```rust
+    /// use rustache::{Data, HashBuilder, VecBuilder};
+    /// let data = Data::from(HashBuilder::new()
+    ///     .insert("name", "Anduin")
+    ///     .insert("cards", VecBuilder::new().push("Heal").push(2)));
+    ///
+    /// assert_eq!("\"name\": \"Anduin\"\n\"cards\":\n  - \"Heal\"\n  - 2\n", data.dump());
+    /// ```
+    pub fn dump(&self) -> String {
+        let mut out = String::new();
+        dump_value(&mut out, self, 0);
+        out
+    }
+}
+
+impl<'a> HashBuilder<'a> {
+    /// An indented tree of the data, see `Data::dump`
+    pub fn dump(&self) -> String {
+        let mut out = String::new();
+        dump_hash(&mut out, &self.data, 0);
+        out
+    }
+}
+
+// writes a value that starts on a line of its own
+fn dump_value(out: &mut String, data: &Data, depth: usize) {
+    match *data {
+        Data::Vector(ref list) if !list.is_empty() => {
+            for item in list {
+                indent(out, depth);
+                out.push('-');
+                dump_item(out, item, depth + 1);
+            }
+        }
+        Data::Hash(ref hash) if !hash.is_empty() => dump_hash(out, hash, depth),
+        Data::Lazy(ref cell) => dump_value(out, cell.force(), depth),
+        _ => {
+            indent(out, depth);
+            dump_scalar(out, data);
+            out.push('\n');
+        }
+    }
+}
+
+// writes a value that follows a key or a list marker, on the same line
+// when it is a single value
+fn dump_item(out: &mut String, data: &Data, depth: usize) {
+    match *data {
+        Data::Vector(ref list) if !list.is_empty() => {
+            out.push('\n');
+            dump_value(out, data, depth);
+        }
+        Data::Hash(ref hash) if !hash.is_empty() => {
+            out.push('\n');
+            dump_value(out, data, depth);
+        }
+        Data::Lazy(ref cell) => dump_item(out, cell.force(), depth),
+        _ => {
+            out.push(' ');
+            dump_scalar(out, data);
+            out.push('\n');
+        }
+    }
+}
+
+fn dump_hash(out: &mut String, hash: &Hash, depth: usize) {
+    for (key, value) in hash {
+        indent(out, depth);
+        let _ = write!(out, "{:?}:", key);
+        dump_item(out, value, depth + 1);
+    }
+}
+
+fn dump_scalar(out: &mut String, data: &Data) {
+    let _ = match *data {
+        Data::String(ref val) => write!(out, "{:?}", val),
+        Data::Bool(val) => write!(out, "{}", val),
+        Data::Integer(val) => write!(out, "{}", val),
+        Data::Unsigned(val) => write!(out, "{}", val),
+        Data::Float(val) => write!(out, "{:?}", val),
+        Data::Vector(_) => write!(out, "[]"),
+        Data::Hash(_) => write!(out, "{{}}"),
+        Data::Lambda(_) | Data::ContextLambda(_) | Data::DataLambda(_) => write!(out, "{}", LAMBDA_PLACEHOLDER),
+        Data::Stream(_) => write!(out, "{}", STREAM_PLACEHOLDER),
+        Data::Lazy(ref cell) => {
+            dump_scalar(out, cell.force());
+            Ok(())
+        }
+        Data::Null => write!(out, "null"),
+    };
+}
+
+fn indent(out: &mut String, depth: usize) {
+    for _ in 0..depth {
+        out.push_str("  ");
+    }
+}
+
+#[cfg(test)]
+mod export_tests {
+    use rustc_serialize::json::ToJson;
+
+    use {Data, HashBuilder, VecBuilder};
+
+    fn hero<'a>() -> HashBuilder<'a> {
+        HashBuilder::new()
+            .insert("name", "Anduin")
+            .insert("age", 21)
+            .insert("title", None::<String>)
+            .insert("cards", VecBuilder::new().push("Heal").push(HashBuilder::new().insert("cost", 1)))
+            .insert_fn("shout", |text| text.to_uppercase())
+            .insert_lazy("deck", || 30)
+            .insert_stream("rows", vec![1, 2])
+    }
+
+    #[test]
+    fn test_to_json() {
+        let json = r#"{"age":21,"cards":["Heal",{"cost":1}],"deck":30,"name":"Anduin","#
+            .to_string() + r#""rows":"<stream>","shout":"<lambda>","title":null}"#;
+
+        assert_eq!(json, hero().to_json().to_string());
+        assert_eq!(json, Data::from(hero()).to_json().to_string());
+    }
+
+    #[test]
+    fn test_dump() {
+        assert_eq!("\"name\": \"Anduin\"\n\
+                    \"age\": 21\n\
+                    \"title\": null\n\
+                    \"cards\":\n  \
+                      - \"Heal\"\n  \
+                      -\n    \
+                        \"cost\": 1\n\
+                    \"shout\": <lambda>\n\
+                    \"deck\": 30\n\
+                    \"rows\": <stream>\n",
+                   hero().dump());
+    }
+
+    #[test]
+    fn test_dump_values() {
+        assert_eq!("2.5\n", Data::from(2.5).dump());
+        assert_eq!("[]\n", Data::from(VecBuilder::new()).dump());
+        assert_eq!("- {}\n- []\n",
+                   Data::from(VecBuilder::new().push(HashBuilder::new()).push(VecBuilder::new()))
+                       .dump());
+    }
+
+    #[test]
+    fn test_dump_keys_are_quoted() {
+        assert_eq!("\"a: b\\n\": 1\n\"\": 2\n",
+                   HashBuilder::new().insert("a: b\n", 1).insert("", 2).dump());
+    }
+}
@@ -0,0 +1,279 @@
+// The standard filters, see `Renderer::enable_filters`.  Filters only see
+// their value through `Context`, so text, numbers and lists are read with
+// `interpolate` and `items`, whatever the data behind them is.
+
+use std::sync::Arc;
+
+use context::Context;
+use {Data, Filter};
+
+const MONTHS: [&str; 12] = ["January", "February", "March", "April", "May", "June", "July",
+                            "August", "September", "October", "November", "December"];
+
+// the standard filter named `name`
+pub fn standard(name: &str) -> Option<Filter> {
+    let f: fn(Option<&dyn Context>, Option<&str>) -> Data<'static> = match name {
+        "upper" => |value, _| Data::from(text(value).to_uppercase()),
+        "lower" => |value, _| Data::from(text(value).to_lowercase()),
+        "capitalize" => capitalize,
+        "trim" => |value, _| Data::from(text(value).trim()),
+        "truncate" => truncate,
+        "default" => default,
+        "join" => join,
+        "length" => length,
+        "number" => number,
+        "date" => date,
+        _ => return None,
+    };
+    Some(Arc::new(f))
+}
+
+// the text of a value, or nothing when it has none
+fn text(value: Option<&dyn Context>) -> String {
+    value.and_then(|value| value.interpolate()).map(|text| text.into_owned()).unwrap_or_default()
+}
+
+fn capitalize(value: Option<&dyn Context>, _: Option<&str>) -> Data<'static> {
+    let text = text(value);
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => Data::from(first.to_uppercase().chain(chars).collect::<String>()),
+        None => Data::from(text),
+    }
+}
+
+fn truncate(value: Option<&dyn Context>, arg: Option<&str>) -> Data<'static> {
+    let text = text(value);
+    match arg.and_then(|arg| arg.trim().parse().ok()) {
+        Some(len) if text.chars().count() > len => {
+            Data::from(text.chars().take(len).collect::<String>() + "...")
+        }
+        _ => Data::from(text),
+    }
+}
+
+fn default(value: Option<&dyn Context>, arg: Option<&str>) -> Data<'static> {
+    let empty = match value {
+        Some(value) => {
+            !value.is_truthy() || value.interpolate().is_some_and(|text| text.is_empty())
+        }
+        None => true,
+    };
+    if empty {
+        Data::from(arg.unwrap_or(""))
+    } else {
+        Data::from(text(value))
+    }
+}
+
+fn join(value: Option<&dyn Context>, arg: Option<&str>) -> Data<'static> {
+    let items = match value.and_then(|value| value.items()) {
+        Some(items) => items,
+        None => return Data::from(text(value)),
+    };
+    let items: Vec<String> = items.map(|item| text(Some(&*item))).collect();
+    Data::from(items.join(arg.unwrap_or(", ")))
+}
+
+fn length(value: Option<&dyn Context>, _: Option<&str>) -> Data<'static> {
+    match value.and_then(|value| value.items()) {
+        Some(items) => Data::from(items.count() as u64),
+        None => Data::from(text(value).chars().count() as u64),
+    }
+}
+
+fn number(value: Option<&dyn Context>, arg: Option<&str>) -> Data<'static> {
+    let text = text(value);
+    let decimals = arg.and_then(|arg| arg.trim().parse().ok()).unwrap_or(0);
+    let num: f64 = match text.trim().parse() {
+        Ok(num) => num,
+        Err(_) => return Data::from(text),
+    };
+
+    let formatted = format!("{:.*}", decimals, num.abs());
+    let (whole, fraction) = match formatted.find('.') {
+        Some(pos) => formatted.split_at(pos),
+        None => (&formatted[..], ""),
+    };
+
+    let mut out = String::new();
+    if num < 0.0 && formatted.chars().any(|c| c != '0' && c != '.') {
+        out.push('-');
+    }
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            out.push(',');
+        }
+        out.push(digit);
+    }
+    out.push_str(fraction);
+    Data::from(out)
+}
+
+fn date(value: Option<&dyn Context>, arg: Option<&str>) -> Data<'static> {
+    let text = text(value);
+    let fields = match parse_date(text.trim()) {
+        Some(fields) => fields,
+        None => return Data::from(text),
+    };
+    let (year, month, day, hour, minute, second) = fields;
+
+    let mut out = String::new();
+    let mut format = arg.unwrap_or("%Y-%m-%d").chars();
+    while let Some(c) = format.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match format.next() {
+            Some('Y') => out.push_str(&year.to_string()),
+            Some('y') => out.push_str(&format!("{:02}", year.rem_euclid(100))),
+            Some('m') => out.push_str(&format!("{:02}", month)),
+            Some('d') => out.push_str(&format!("{:02}", day)),
+            Some('e') => out.push_str(&day.to_string()),
+            Some('B') => out.push_str(MONTHS[month as usize - 1]),
+            Some('b') => out.push_str(&MONTHS[month as usize - 1][..3]),
+            Some('H') => out.push_str(&format!("{:02}", hour)),
+            Some('M') => out.push_str(&format!("{:02}", minute)),
+            Some('S') => out.push_str(&format!("{:02}", second)),
+            Some(other) => {
+                out.push('%');
+                out.push(other);
+            }
+            None => out.push('%'),
+        }
+    }
+    Data::from(out)
+}
+
+// (year, month, day, hour, minute, second) of a Unix timestamp in UTC, or
+// of a date written as YYYY-MM-DD with an optional THH:MM:SS or HH:MM:SS
+fn parse_date(text: &str) -> Option<(i64, u32, u32, u32, u32, u32)> {
+    if let Ok(timestamp) = text.parse::<i64>() {
+        let days = timestamp.div_euclid(86400);
+        let seconds = timestamp.rem_euclid(86400) as u32;
+        let (year, month, day) = civil_from_days(days);
+        return Some((year, month, day, seconds / 3600, seconds / 60 % 60, seconds % 60));
+    }
+
+    let (date, time) = match text.find(['T', ' ']) {
+        Some(pos) => (&text[..pos], Some(&text[pos + 1..])),
+        None => (text, None),
+    };
+    let mut date = date.split('-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = match (date.next(), date.next(), date.next(), date.next()) {
+        (Some(Some(year)), Some(Some(month)), Some(Some(day)), None) => (year, month, day),
+        _ => return None,
+    };
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+
+    let (mut hour, mut minute, mut second) = (0, 0, 0);
+    if let Some(time) = time {
+        let mut time = time.trim_end_matches('Z').split(':').map(|part| part.parse::<u32>().ok());
+        match (time.next(), time.next(), time.next()) {
+            (Some(Some(h)), Some(Some(m)), Some(Some(s))) => {
+                hour = h;
+                minute = m;
+                second = s;
+            }
+            (Some(Some(h)), Some(Some(m)), None) => {
+                hour = h;
+                minute = m;
+            }
+            _ => return None,
+        }
+        if hour > 23 || minute > 59 || second > 59 {
+            return None;
+        }
+    }
+
+    Some((year, month as u32, day as u32, hour, minute, second))
+}
+
+// the number of days in a month of the proleptic Gregorian calendar
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// the date `days` days after 1970-01-01, in the proleptic Gregorian
+// calendar, see http://howardhinnant.github.io/date_algorithms.html
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod filters_tests {
+    use context::Context;
+    use filters::standard;
+    use {Data, VecBuilder};
+
+    fn apply(name: &str, value: Data, arg: Option<&str>) -> String {
+        let f = standard(name).unwrap();
+        let result = f(Some(&value), arg);
+        result.interpolate().unwrap().into_owned()
+    }
+
+    #[test]
+    fn test_number() {
+        assert_eq!("1,234,568", apply("number", Data::from(1234567.8), None));
+        assert_eq!("1,234.50", apply("number", Data::from(1234.5), Some("2")));
+        assert_eq!("-999", apply("number", Data::from(-999), None));
+        assert_eq!("0", apply("number", Data::from(-0.2), None));
+        assert_eq!("n/a", apply("number", Data::from("n/a"), None));
+    }
+
+    #[test]
+    fn test_date() {
+        assert_eq!("2009-02-13 23:31:30",
+                   apply("date", Data::from(1234567890), Some("%Y-%m-%d %H:%M:%S")));
+        assert_eq!("29 February 2000", apply("date", Data::from("2000-02-29"), Some("%e %B %Y")));
+        assert_eq!("Dec 31, 69", apply("date", Data::from(-1), Some("%b %d, %y")));
+        assert_eq!("08:05", apply("date", Data::from("2020-01-01T08:05:00Z"), Some("%H:%M")));
+        assert_eq!("2020-01-01", apply("date", Data::from("2020-01-01T08:05"), None));
+        assert_eq!("soon", apply("date", Data::from("soon"), None));
+        assert_eq!("2020-01-01T24:00", apply("date", Data::from("2020-01-01T24:00"), Some("%H:%M")));
+        assert_eq!("2020-01-01 08:61:00",
+                   apply("date", Data::from("2020-01-01 08:61:00"), Some("%H:%M")));
+        assert_eq!("2020-13-01", apply("date", Data::from("2020-13-01"), None));
+        assert_eq!("2021-02-31", apply("date", Data::from("2021-02-31"), None));
+        assert_eq!("2100-02-29", apply("date", Data::from("2100-02-29"), None));
+        assert_eq!("2024-04-31", apply("date", Data::from("2024-04-31"), None));
+        assert_eq!("29 Feb 2024", apply("date", Data::from("2024-02-29"), Some("%e %b %Y")));
+    }
+
+    #[test]
+    fn test_list_filters() {
+        let list = || Data::from(VecBuilder::new().push("Mage").push(2));
+
+        assert_eq!("Mage, 2", apply("join", list(), None));
+        assert_eq!("Mage/2", apply("join", list(), Some("/")));
+        assert_eq!("2", apply("length", list(), None));
+        assert_eq!("5", apply("length", Data::from("Druid"), None));
+    }
+
+    #[test]
+    fn test_default() {
+        let f = standard("default").unwrap();
+
+        assert_eq!(Data::from("none"), f(None, Some("none")));
+        assert_eq!(Data::from("none"), f(Some(&Data::from("")), Some("none")));
+        assert_eq!(Data::from("none"), f(Some(&Data::from(false)), Some("none")));
+        assert_eq!(Data::from("0"), f(Some(&Data::from(0)), Some("none")));
+    }
+}
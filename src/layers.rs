@@ -0,0 +1,61 @@
+use context::Context;
+
+/// `Layers` stacks several sources of data, such as site wide defaults,
+/// then page data, then request data.  A name is looked up in the last
+/// layer pushed first and falls through to the layers before it, so the
+/// layers are never merged or copied.
+///
+/// ```rust
+/// use rustache::{HashBuilder, Layers, Render};
+/// use std::io::Cursor;
+///
+/// let site = HashBuilder::new()
+///     .insert("site", "Hearthstone")
+///     .insert("title", "Home");
+/// let page = HashBuilder::new()
+///     .insert("title", "Decks");
+///
+/// let mut out = Cursor::new(Vec::new());
+/// Layers::new().push(&site).push(&page).render("{{ title }} - {{ site }}", &mut out).unwrap();
+///
+/// assert_eq!("Decks - Hearthstone", String::from_utf8(out.into_inner()).unwrap());
+/// ```
+pub struct Layers<'a> {
+    #[doc(hidden)]
+    pub layers: Vec<Box<dyn Context + 'a>>,
+    #[doc(hidden)]
+    pub partials_path: &'a str,
+}
+
+impl<'a> Layers<'a> {
+    /// Create a new `Layers` instance without any data
+    pub fn new() -> Layers<'a> {
+        Layers {
+            layers: Vec::new(),
+            partials_path: "",
+        }
+    }
+
+    /// Add a layer of data, which takes precedence over the layers added
+    /// before it.  Any `Context` can be a layer, including a `HashBuilder`
+    /// or a `&dyn Context`, although the partials path of a `HashBuilder`
+    /// is not used.
+    pub fn push<C: Context + ?Sized>(mut self, layer: &'a C) -> Layers<'a> {
+        self.layers.push(Box::new(layer));
+        self
+    }
+
+    /// Set a path to partials data
+    pub fn set_partials_path(self, path: &'a str) -> Layers<'a> {
+        Layers {
+            layers: self.layers,
+            partials_path: path,
+        }
+    }
+}
+
+impl<'a> Default for Layers<'a> {
+    fn default() -> Layers<'a> {
+        Layers::new()
+    }
+}
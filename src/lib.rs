@@ -6,16 +6,33 @@
 //! Rustache is a flexible template engine for Rust.
 #[macro_use]
 extern crate error_chain;
+extern crate indexmap;
 extern crate rustc_serialize;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde_json")]
+extern crate serde_json;
 
 use std::fmt;
-use std::cell::RefCell;
-use std::collections::HashMap;
+use std::iter::Peekable;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, OnceLock};
 use std::convert::From;
 use std::string;
 
+use indexmap::IndexMap;
+
 pub use build::{HashBuilder, VecBuilder};
 pub use rustache::Render;
+pub use context::{Context, Entries, Items};
+pub use layers::Layers;
+pub use renderer::{Renderer, Truthiness};
+pub use template::LambdaContext;
+pub use typed::TypedTemplate;
+#[cfg(feature = "serde")]
+pub use ser::to_data;
+#[doc(hidden)]
+pub use typed::{Fields, FieldShape, FieldsOf, NoFields};
 
 /// Alias for Result<T, `RustacheError`>
 pub use errors::*;
@@ -25,19 +42,78 @@ pub use errors::*;
 pub enum Data<'a> {
     String(string::String),
     Bool(bool),
-    Integer(i32),
+    Integer(i64),
+    Unsigned(u64),
     Float(f64),
     Vector(self::Vector<'a>),
     Hash(self::Hash<'a>),
-    Lambda(RefCell<self::Lambda<'a>>),
+    Lambda(self::SharedLambda<'a>),
+    ContextLambda(self::ContextLambda<'a>),
+    DataLambda(self::DataLambda<'a>),
+    Lazy(Arc<Memo<'a>>),
+    Stream(Arc<Mutex<Peekable<self::Stream<'a>>>>),
+    Null,
+}
+
+// Data computed by a function the first time it is needed, which keeps the
+// value for every later use
+#[doc(hidden)]
+pub struct Memo<'a> {
+    value: OnceLock<Data<'a>>,
+    init: Mutex<Option<self::Lazy<'a>>>,
+}
+
+impl<'a> Memo<'a> {
+    fn new(f: self::Lazy<'a>) -> Memo<'a> {
+        Memo {
+            value: OnceLock::new(),
+            init: Mutex::new(Some(f)),
+        }
+    }
+
+    // the value, which is computed by the first caller
+    fn force(&self) -> &Data<'a> {
+        self.value.get_or_init(|| {
+            let f = self.init.lock().unwrap_or_else(|err| err.into_inner()).take();
+            f.expect("a lazy value panicked while it was computed")()
+        })
+    }
+}
+
+impl<'a> Deref for Memo<'a> {
+    type Target = Data<'a>;
+
+    fn deref(&self) -> &Data<'a> {
+        self.force()
+    }
 }
 
 /// Alias for mustache data vectors
 pub type Vector<'a> = Vec<Data<'a>>;
-/// Alias for mustache data hashes
-pub type Hash<'a> = HashMap<string::String, Data<'a>>;
+/// Alias for mustache data hashes, which keep their keys in the order
+/// they were inserted
+pub type Hash<'a> = IndexMap<string::String, Data<'a>>;
+/// Alias for the entry for a key of a mustache data hash, see
+/// `HashBuilder::entry`
+pub type Entry<'h, 'a> = indexmap::map::Entry<'h, string::String, Data<'a>>;
 /// Alias for a Lambda functions to transform data
-pub type Lambda<'a> = &'a mut FnMut(string::String) -> string::String;
+pub type Lambda<'a> = &'a mut (dyn FnMut(string::String) -> string::String + Send);
+/// Alias for an owned Lambda function, which can be shared between threads
+pub type SharedLambda<'a> = Arc<dyn Fn(string::String) -> string::String + Send + Sync + 'a>;
+/// Alias for a Lambda function that renders with the data around its tag
+pub type ContextLambda<'a> = Arc<dyn Fn(&str, &mut LambdaContext) -> string::String + Send + Sync + 'a>;
+/// Alias for a Lambda function that returns the data to render its tag with
+pub type DataLambda<'a> = Arc<dyn Fn(&mut LambdaContext) -> Data<'a> + Send + Sync + 'a>;
+/// Alias for a function computing data the first time it is rendered
+pub type Lazy<'a> = Box<dyn FnOnce() -> Data<'a> + Send + 'a>;
+/// Alias for an iterator producing list items while they are rendered
+pub type Stream<'a> = Box<dyn Iterator<Item = Data<'a>> + Send + 'a>;
+/// Alias for a filter, which transforms a value and is given the text of
+/// its argument, see `Renderer::filter`
+pub type Filter = Arc<dyn Fn(Option<&dyn Context>, Option<&str>) -> Data<'static> + Send + Sync>;
+/// Alias for a function that decides whether a section's data is true,
+/// see `Truthiness::Custom`
+pub type TruthyFn = Arc<dyn Fn(&dyn Context) -> bool + Send + Sync>;
 
 impl<'a, 'b> From<&'b str> for Data<'a> {
     fn from(v: &'b str) -> Data<'a> {
@@ -57,9 +133,41 @@ impl<'a> From<bool> for Data<'a> {
     }
 }
 
-impl<'a> From<i32> for Data<'a> {
-    fn from(v: i32) -> Data<'a> {
-        Data::Integer(v)
+macro_rules! from_signed {
+    ($($ty:ty),*) => {
+        $(
+            impl<'a> From<$ty> for Data<'a> {
+                fn from(v: $ty) -> Data<'a> {
+                    Data::Integer(v as i64)
+                }
+            }
+        )*
+    }
+}
+
+// unsigned values are only kept apart when they don't fit into an `i64`
+macro_rules! from_unsigned {
+    ($($ty:ty),*) => {
+        $(
+            impl<'a> From<$ty> for Data<'a> {
+                fn from(v: $ty) -> Data<'a> {
+                    if v as u64 <= i64::MAX as u64 {
+                        Data::Integer(v as i64)
+                    } else {
+                        Data::Unsigned(v as u64)
+                    }
+                }
+            }
+        )*
+    }
+}
+
+from_signed!(i8, i16, i32, i64, isize);
+from_unsigned!(u8, u16, u32, u64, usize);
+
+impl<'a> From<f32> for Data<'a> {
+    fn from(v: f32) -> Data<'a> {
+        Data::Float(v as f64)
     }
 }
 
@@ -69,6 +177,15 @@ impl<'a> From<f64> for Data<'a> {
     }
 }
 
+impl<'a, T: Into<Data<'a>>> From<Option<T>> for Data<'a> {
+    fn from(v: Option<T>) -> Data<'a> {
+        match v {
+            Some(v) => v.into(),
+            None => Data::Null,
+        }
+    }
+}
+
 impl<'a> From<self::Vector<'a>> for Data<'a> {
     fn from(v: self::Vector<'a>) -> Data<'a> {
         Data::Vector(v)
@@ -81,9 +198,33 @@ impl<'a> From<self::Hash<'a>> for Data<'a> {
     }
 }
 
+// A borrowed lambda is `FnMut`, so it is called behind a lock to keep data
+// holding it `Send + Sync`.
 impl<'a> From<self::Lambda<'a>> for Data<'a> {
     fn from(v: self::Lambda<'a>) -> Data<'a> {
-        Data::Lambda(RefCell::new(v))
+        let f = Mutex::new(v);
+        Data::Lambda(Arc::new(move |text| {
+            let mut f = f.lock().unwrap_or_else(|err| err.into_inner());
+            (*f)(text)
+        }))
+    }
+}
+
+impl<'a> From<self::SharedLambda<'a>> for Data<'a> {
+    fn from(v: self::SharedLambda<'a>) -> Data<'a> {
+        Data::Lambda(v)
+    }
+}
+
+impl<'a> From<self::ContextLambda<'a>> for Data<'a> {
+    fn from(v: self::ContextLambda<'a>) -> Data<'a> {
+        Data::ContextLambda(v)
+    }
+}
+
+impl<'a> From<self::DataLambda<'a>> for Data<'a> {
+    fn from(v: self::DataLambda<'a>) -> Data<'a> {
+        Data::DataLambda(v)
     }
 }
 
@@ -93,18 +234,56 @@ impl<'a> From<self::Lambda<'a>> for Data<'a> {
 impl<'a> PartialEq for Data<'a> {
     fn eq(&self, other: &Data<'a>) -> bool {
         match (self, other) {
+            // lazy data is compared by its value, so comparing computes it
+            // unless both sides share the same lazy value
+            (Data::Lazy(val0), Data::Lazy(val1)) if Arc::ptr_eq(val0, val1) => true,
+            (&Data::Lazy(ref val0), _) => ***val0 == *other,
+            (_, &Data::Lazy(ref val1)) => *self == ***val1,
             (&Data::String(ref val0), &Data::String(ref val1)) => val0 == val1,
             (&Data::Bool(ref val0), &Data::Bool(ref val1)) => val0 == val1,
             (&Data::Integer(ref val0), &Data::Integer(ref val1)) => val0 == val1,
+            (&Data::Unsigned(ref val0), &Data::Unsigned(ref val1)) => val0 == val1,
+            (&Data::Integer(val0), &Data::Unsigned(val1)) |
+            (&Data::Unsigned(val1), &Data::Integer(val0)) => val0 >= 0 && val0 as u64 == val1,
             (&Data::Float(ref val0), &Data::Float(ref val1)) => val0 == val1,
             (&Data::Vector(ref val0), &Data::Vector(ref val1)) => val0 == val1,
             (&Data::Hash(ref val0), &Data::Hash(ref val1)) => val0 == val1,
-            (&Data::Lambda(_), &Data::Lambda(_)) => panic!("Can't compare closures"),
+            // closures can't be compared, so lambdas are only equal when
+            // they share the same function
+            (&Data::Lambda(ref val0), &Data::Lambda(ref val1)) => Arc::ptr_eq(val0, val1),
+            (&Data::ContextLambda(ref val0), &Data::ContextLambda(ref val1)) => Arc::ptr_eq(val0, val1),
+            (&Data::DataLambda(ref val0), &Data::DataLambda(ref val1)) => Arc::ptr_eq(val0, val1),
+            // the same goes for streams, whose items can only be produced once
+            (Data::Stream(val0), Data::Stream(val1)) => Arc::ptr_eq(val0, val1),
+            (&Data::Null, &Data::Null) => true,
             (_, _) => false,
         }
     }
 }
 
+// Lambdas, lazy data and streams are shared rather than copied.  A lazy
+// value is computed at most once, by whichever copy needs it first, and
+// the copies of a stream take turns producing the same items.
+impl<'a> Clone for Data<'a> {
+    fn clone(&self) -> Data<'a> {
+        match *self {
+            Data::String(ref val) => Data::String(val.clone()),
+            Data::Bool(val) => Data::Bool(val),
+            Data::Integer(val) => Data::Integer(val),
+            Data::Unsigned(val) => Data::Unsigned(val),
+            Data::Float(val) => Data::Float(val),
+            Data::Vector(ref val) => Data::Vector(val.clone()),
+            Data::Hash(ref val) => Data::Hash(val.clone()),
+            Data::Lambda(ref val) => Data::Lambda(val.clone()),
+            Data::ContextLambda(ref val) => Data::ContextLambda(val.clone()),
+            Data::DataLambda(ref val) => Data::DataLambda(val.clone()),
+            Data::Lazy(ref val) => Data::Lazy(val.clone()),
+            Data::Stream(ref val) => Data::Stream(val.clone()),
+            Data::Null => Data::Null,
+        }
+    }
+}
+
 // Implementing custom Show for Data
 impl<'a> fmt::Debug for Data<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -112,10 +291,16 @@ impl<'a> fmt::Debug for Data<'a> {
             Data::String(ref val) => write!(f, "String({:?})", val),
             Data::Bool(val) => write!(f, "Boolean({:?})", val),
             Data::Integer(ref val) => write!(f, "Integer({:?})", val),
+            Data::Unsigned(ref val) => write!(f, "Unsigned({:?})", val),
             Data::Float(ref val) => write!(f, "Float({:?})", val),
             Data::Vector(ref val) => write!(f, "Vector({:?})", val),
             Data::Hash(ref val) => write!(f, "Hash({:?})", val),
             Data::Lambda(_) => write!(f, "Lambda(...)"),
+            Data::ContextLambda(_) => write!(f, "ContextLambda(...)"),
+            Data::DataLambda(_) => write!(f, "DataLambda(...)"),
+            Data::Lazy(_) => write!(f, "Lazy(...)"),
+            Data::Stream(_) => write!(f, "Stream(...)"),
+            Data::Null => write!(f, "Null"),
         }
     }
 }
@@ -127,3 +312,12 @@ mod compiler;
 mod parser;
 mod build;
 mod template;
+mod context;
+mod export;
+mod layers;
+mod query;
+mod renderer;
+mod filters;
+mod typed;
+#[cfg(feature = "serde")]
+mod ser;
@@ -4,7 +4,7 @@
 // to seek out appropriate data for injection.
 
 use compiler::Token;
-use compiler::Token::{Text, Variable, OTag, CTag, Raw, Partial, Comment};
+use compiler::Token::{Text, Variable, OTag, CTag, Raw, Partial, Delimiters, Comment};
 use self::Node::*;
 use self::ParserStatus::*;
 
@@ -17,7 +17,7 @@ use self::ParserStatus::*;
 pub enum Node<'a> {
     Static(&'a str), // (text)
     Value(&'a str, String), // (name, tag)
-    Section(&'a str, Vec<Node<'a>>, bool, String, String), /* (name, children, inverted, otag, ctag) */
+    Section(&'a str, Vec<Node<'a>>, bool, String, String, (&'a str, &'a str)), /* (name, children, inverted, otag, ctag, delimiters) */
     Unescaped(&'a str, String), // (name, tag)
     Part(&'a str, &'a str), // // (name, tag)
 }
@@ -48,16 +48,24 @@ impl LocalStringExt for str {
 // Parse list of tokens into instruction nodes
 // Section nodes will be handled recursively
 pub fn parse_nodes<'a>(list: &[Token<'a>]) -> Vec<Node<'a>> {
+    parse_nodes_with(list, ("{{", "}}"))
+}
+
+// Parse list of tokens whose tags start out with the given delimiters.
+// Sections remember the delimiters they were opened with, which a section
+// lambda's result is compiled with.
+pub fn parse_nodes_with<'a>(list: &[Token<'a>], delimiters: (&'a str, &'a str)) -> Vec<Node<'a>> {
     let mut nodes: Vec<Node> = vec![];
     let mut it = list.iter().enumerate().peekable();
     let mut status = Parse;
+    let mut delimiters = delimiters;
 
     // Iterate while still nodes in the list
     while let Some((i, token)) = it.next() {
         match *token {
             Text(text) => nodes.push(parse_text_node(text, &mut status)),
-            Variable(name, raw) => nodes.push(parse_variable_node(name, raw)),
-            Raw(name, raw) => nodes.push(parse_raw_node(name, raw)),
+            Variable(name, raw) => nodes.push(parse_variable_node(name, raw, delimiters)),
+            Raw(name, raw) => nodes.push(parse_raw_node(name, raw, delimiters)),
             Partial(name, raw) => nodes.push(Part(name, raw)),
             // Unopened closing tags are ignored
             // TODO: Return a parser error?
@@ -78,10 +86,11 @@ pub fn parse_nodes<'a>(list: &[Token<'a>]) -> Vec<Node<'a>> {
                         CTag(title, temp) => {
                             if title == name && otag_count == 1 {
                                 nodes.push(Section(name,
-                                                   parse_nodes(&children).clone(),
+                                                   parse_nodes_with(&children, delimiters),
                                                    inverted,
                                                    raw.to_string(),
-                                                   temp.to_string()));
+                                                   temp.to_string(),
+                                                   delimiters));
                                 break;
                             } else if title == name && otag_count > 1 {
                                 otag_count -= 1;
@@ -105,6 +114,32 @@ pub fn parse_nodes<'a>(list: &[Token<'a>]) -> Vec<Node<'a>> {
                     it.next();
                     count -= 1;
                 }
+
+                // Delimiters changed inside the section stay changed after it
+                for item in children.iter() {
+                    if let Delimiters(open, close) = *item {
+                        delimiters = (open, close);
+                    }
+                }
+            }
+            Delimiters(open, close) => {
+                delimiters = (open, close);
+                // A set delimiter tag on a line of its own takes the line
+                // with it
+                let standalone = match it.peek() {
+                    Some(&(_, &Text(next))) => next.is_whitespace() && next.contains('\n'),
+                    Some(_) => false,
+                    None => true,
+                };
+                if standalone && trim_standalone_line(&mut nodes) {
+                    if let Some(&(_, &Text(next))) = it.peek() {
+                        it.next();
+                        let rest = &next[next.find('\n').unwrap() + 1..];
+                        if !rest.is_empty() {
+                            nodes.push(parse_text_node(rest, &mut status));
+                        }
+                    }
+                }
             }
             Comment => {
                 // Check the next element for whitespace
@@ -137,26 +172,84 @@ fn parse_text_node<'a>(text: &'a str, status: &mut ParserStatus) -> Node<'a> {
     Static(text)
 }
 
+// Removes the indentation before a tag if nothing else is on its line,
+// returning whether the tag started its line
+fn trim_standalone_line(nodes: &mut Vec<Node>) -> bool {
+    let first = nodes.len() == 1;
+    let text = match nodes.last() {
+        None => return true,
+        Some(&Static(text)) if text.is_whitespace() => text,
+        Some(_) => return false,
+    };
+
+    match text.rfind('\n') {
+        Some(pos) => {
+            nodes.pop();
+            nodes.push(Static(&text[..pos + 1]));
+            true
+        }
+        None if first => {
+            nodes.pop();
+            true
+        }
+        None => false,
+    }
+}
+
+// Whether a name uses the dot notation shorthand.  Names piping their
+// value through filters are left whole, see `Renderer::enable_filters`, as
+// are names reaching outer contexts, see `Renderer::enable_context_paths`,
+// and names picking an item of a list, which the template looks up one
+// part at a time instead of rendering the list
+fn is_dotted(name: &str) -> bool {
+    name.contains('.') && !name.contains('|') && !is_context_path(name) &&
+    !name.split('.').any(is_index) && !is_implicit(name)
+}
+
+// Whether a part of a dotted name is a number, which picks an item of a
+// list
+pub fn is_index(part: &str) -> bool {
+    !part.is_empty() && part.chars().all(|c| c.is_ascii_digit())
+}
+
+// Whether a name the parser leaves whole is looked up one part at a time,
+// as section names and the names above are
+pub fn is_path(name: &str) -> bool {
+    is_context_path(name) || (name.contains('.') && !name.contains('|') && !is_implicit(name))
+}
+
+// Whether a name is `.`, which names the data being rendered, such as the
+// current item of a list
+pub fn is_implicit(name: &str) -> bool {
+    name == "."
+}
+
+// Whether a name starts from an outer context, as `../name` and
+// `@root.name` do
+pub fn is_context_path(name: &str) -> bool {
+    name.starts_with("../") || name.starts_with("@root.")
+}
+
 // Helper function for handling the creation of a variable node
-fn parse_variable_node<'a>(name: &'a str, raw: &'a str) -> Node<'a> {
-    if name.contains('.') {
+fn parse_variable_node<'a>(name: &'a str, raw: &'a str, delimiters: (&'a str, &'a str)) -> Node<'a> {
+    if is_dotted(name) {
         let parts: Vec<&str> = name.split('.').collect();
-        handle_dot_notation(&parts[..], false, false)
+        handle_dot_notation(&parts[..], false, false, delimiters)
     } else {
         Value(name, raw.to_string())
     }
 }
 
 // Helper function for handling the creation of an unescaped variable node
-fn parse_raw_node<'a>(name: &'a str, raw: &'a str) -> Node<'a> {
-    let dot_notation = name.contains('.');
+fn parse_raw_node<'a>(name: &'a str, raw: &'a str, delimiters: (&'a str, &'a str)) -> Node<'a> {
+    let dot_notation = is_dotted(name);
     let ampersand = raw.contains('&');
     if dot_notation {
         let parts: Vec<&str> = name.split('.').collect();
         if ampersand {
-            handle_dot_notation(&parts[..], true, true)
+            handle_dot_notation(&parts[..], true, true, delimiters)
         } else {
-            handle_dot_notation(&parts[..], true, false)
+            handle_dot_notation(&parts[..], true, false, delimiters)
         }
     } else {
         Unescaped(name, raw.to_string())
@@ -196,8 +289,13 @@ fn parse_comment_node<'a>(token: &Token,
 }
 
 // Recursively handle tag names that utilize dot notation shorthand
-fn handle_dot_notation<'a>(parts: &[&'a str], unescaped: bool, amp: bool) -> Node<'a> {
+fn handle_dot_notation<'a>(parts: &[&'a str],
+                           unescaped: bool,
+                           amp: bool,
+                           delimiters: (&'a str, &'a str))
+                           -> Node<'a> {
     let variable = parts[0];
+    let (open, close) = delimiters;
     // Determine if the remaining portion of the tag name is the
     // variable or another section.
     if parts.len() == 1 {
@@ -205,38 +303,64 @@ fn handle_dot_notation<'a>(parts: &[&'a str], unescaped: bool, amp: bool) -> Nod
         if unescaped {
             if amp {
                 // {{&variable}}
-                Unescaped(variable, format!("{{{{&{}}}}}", variable))
+                Unescaped(variable, format!("{}&{}{}", open, variable, close))
             } else {
                 // {{{variable}}}
-                Unescaped(variable, format!("{{{{{{{}}}}}}}", variable))
+                Unescaped(variable, format!("{}{{{}}}{}", open, variable, close))
             }
         } else {
             // {{variable}}
-            Value(variable, format!("{{{{{}}}}}", variable))
+            Value(variable, format!("{}{}{}", open, variable, close))
         }
     } else {
         // {{#variable}}
-        let otag = format!("{{{{#{}}}}}", variable);
+        let otag = format!("{}#{}{}", open, variable, close);
         // {{/variable}}
-        let ctag = format!("{{{{/{}}}}}", variable);
+        let ctag = format!("{}/{}{}", open, variable, close);
 
         // Enter recursion and assign the results as children.
         Section(variable,
-                vec![handle_dot_notation(&parts[1..], unescaped, amp)],
+                vec![handle_dot_notation(&parts[1..], unescaped, amp, delimiters)],
                 false,
                 otag,
-                ctag)
+                ctag,
+                delimiters)
     }
 }
 
 #[cfg(test)]
 mod parser_tests {
     use compiler::Token;
-    use compiler::Token::{Text, Variable, OTag, CTag, Raw, Partial};
+    use compiler::Token::{Text, Variable, OTag, CTag, Raw, Partial, Delimiters};
     use parser;
     use parser::Node;
     use parser::Node::{Static, Value, Section, Unescaped, Part};
 
+    #[test]
+    fn parse_context_paths_left_whole() {
+        let tokens: Vec<Token> = vec![Variable("../order.id", "{{ ../order.id }}"),
+                                      Raw("@root.name", "{{{ @root.name }}}")];
+        let nodes = parser::parse_nodes(&tokens);
+        let expected: Vec<Node> = vec![Value("../order.id", "{{ ../order.id }}".to_string()),
+                                       Unescaped("@root.name", "{{{ @root.name }}}".to_string())];
+        assert_eq!(nodes, expected);
+    }
+
+    #[test]
+    fn parse_list_indexes_left_whole() {
+        let tokens: Vec<Token> = vec![Variable("images.0.url", "{{ images.0.url }}"),
+                                      Variable("images.first", "{{images.first}}")];
+        let nodes = parser::parse_nodes(&tokens);
+        let expected: Vec<Node> = vec![Value("images.0.url", "{{ images.0.url }}".to_string()),
+                                       Section("images",
+                                               vec![Value("first", "{{first}}".to_string())],
+                                               false,
+                                               "{{#images}}".to_string(),
+                                               "{{/images}}".to_string(),
+                                               ("{{", "}}"))];
+        assert_eq!(nodes, expected);
+    }
+
     #[test]
     fn parse_dot_notation_simple() {
         let tokens: Vec<Token> = vec![Variable("section.child_tag", "{{ section.child_tag }}")];
@@ -246,7 +370,8 @@ mod parser_tests {
                                                           "{{child_tag}}".to_string())],
                                                false,
                                                "{{#section}}".to_string(),
-                                               "{{/section}}".to_string())];
+                                               "{{/section}}".to_string(),
+                                               ("{{", "}}"))];
         assert_eq!(nodes, expected);
     }
 
@@ -259,7 +384,8 @@ mod parser_tests {
                                                               "{{{child_tag}}}".to_string())],
                                                false,
                                                "{{#section}}".to_string(),
-                                               "{{/section}}".to_string())];
+                                               "{{/section}}".to_string(),
+                                               ("{{", "}}"))];
         assert_eq!(nodes, expected);
     }
 
@@ -272,7 +398,8 @@ mod parser_tests {
                                                               "{{&child_tag}}".to_string())],
                                                false,
                                                "{{#section}}".to_string(),
-                                               "{{/section}}".to_string())];
+                                               "{{/section}}".to_string(),
+                                               ("{{", "}}"))];
         assert_eq!(nodes, expected);
     }
 
@@ -286,10 +413,12 @@ mod parser_tests {
                                                                        "{{tag}}".to_string())],
                                                             false,
                                                             "{{#child}}".to_string(),
-                                                            "{{/child}}".to_string())],
+                                                            "{{/child}}".to_string(),
+                                                            ("{{", "}}"))],
                                                false,
                                                "{{#section}}".to_string(),
-                                               "{{/section}}".to_string())];
+                                               "{{/section}}".to_string(),
+                                               ("{{", "}}"))];
         assert_eq!(nodes, expected);
     }
 
@@ -303,10 +432,12 @@ mod parser_tests {
                                       vec![Unescaped("tag", "{{{tag}}}".to_string())],
                                       false,
                                       "{{#child}}".to_string(),
-                                      "{{/child}}".to_string())],
+                                      "{{/child}}".to_string(),
+                                      ("{{", "}}"))],
                          false,
                          "{{#section}}".to_string(),
-                         "{{/section}}".to_string())];
+                         "{{/section}}".to_string(),
+                         ("{{", "}}"))];
         assert_eq!(nodes, expected);
     }
 
@@ -320,10 +451,12 @@ mod parser_tests {
                                       vec![Unescaped("tag", "{{&tag}}".to_string())],
                                       false,
                                       "{{#child}}".to_string(),
-                                      "{{/child}}".to_string())],
+                                      "{{/child}}".to_string(),
+                                      ("{{", "}}"))],
                          false,
                          "{{#section}}".to_string(),
-                         "{{/section}}".to_string())];
+                         "{{/section}}".to_string(),
+                         ("{{", "}}"))];
         assert_eq!(nodes, expected);
     }
 
@@ -354,7 +487,8 @@ mod parser_tests {
                                                           "{{ child_tag }}".to_string())],
                                                false,
                                                "{{# section }}".to_string(),
-                                               "{{/ section }}".to_string())];
+                                               "{{/ section }}".to_string(),
+                                               ("{{", "}}"))];
         assert_eq!(nodes, expected);
     }
 
@@ -369,7 +503,8 @@ mod parser_tests {
                                                           "{{ child_tag }}".to_string())],
                                                true,
                                                "{{^ inverted }}".to_string(),
-                                               "{{/ inverted }}".to_string())];
+                                               "{{/ inverted }}".to_string(),
+                                               ("{{", "}}"))];
         assert_eq!(nodes, expected);
     }
 
@@ -405,11 +540,55 @@ mod parser_tests {
                                    vec![Value("child_tag", "{{ child_tag }}".to_string())],
                                    false,
                                    "{{# section }}".to_string(),
-                                   "{{/ section }}".to_string());
+                                   "{{/ section }}".to_string(),
+                                   ("{{", "}}"));
         let file_node = Part("new", "{{> new }}");
         let undescaped_node = Unescaped("unescaped", "{{& unescaped }}".to_string());
         let expected: Vec<Node> =
             vec![static_node, value_node, section_node, file_node, undescaped_node];
         assert_eq!(nodes, expected);
     }
+
+    #[test]
+    fn parse_delimiters() {
+        let tokens: Vec<Token> = vec![Delimiters("|", "|"),
+                                      OTag("section", false, "|# section |"),
+                                      Variable("section.child_tag", "| section.child_tag |"),
+                                      CTag("section", "|/ section |")];
+        let nodes = parser::parse_nodes(&tokens);
+        let child = Section("section",
+                            vec![Value("child_tag", "|child_tag|".to_string())],
+                            false,
+                            "|#section|".to_string(),
+                            "|/section|".to_string(),
+                            ("|", "|"));
+        let expected: Vec<Node> = vec![Section("section",
+                                               vec![child],
+                                               false,
+                                               "|# section |".to_string(),
+                                               "|/ section |".to_string(),
+                                               ("|", "|"))];
+        assert_eq!(nodes, expected);
+    }
+
+    #[test]
+    fn parse_standalone_delimiters() {
+        let tokens: Vec<Token> = vec![Text("Begin."),
+                                      Text("\n  "),
+                                      Delimiters("|", "|"),
+                                      Text("\n"),
+                                      Text("End."),
+                                      Text(" "),
+                                      Delimiters("{{", "}}"),
+                                      Text(" "),
+                                      Text("Done.")];
+        let nodes = parser::parse_nodes(&tokens);
+        let expected: Vec<Node> = vec![Static("Begin."),
+                                       Static("\n"),
+                                       Static("End."),
+                                       Static(" "),
+                                       Static(" "),
+                                       Static("Done.")];
+        assert_eq!(nodes, expected);
+    }
 }
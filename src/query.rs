@@ -0,0 +1,225 @@
+// Reading and patching data after it has been built.  Hashes are indexed
+// by key and vectors by position, so a path such as `a.b.0.c` can reach
+// into both.  Lazy data is computed when it is looked into, and replaced
+// by its value before that is changed, so other copies keep the original.
+
+use std::iter::FromIterator;
+
+use {Data, Hash};
+
+impl<'a> Data<'a> {
+    /// The value under `key` in a hash, or at the position `key` in a
+    /// vector
+    ///
+    /// ```rust
+    /// use rustache::{Data, VecBuilder};
+    /// let data = Data::from(VecBuilder::new().push("Mage").push("Druid"));
+    ///
+    /// assert_eq!(Some(&Data::from("Druid")), data.get("1"));
+    /// ```
+    pub fn get(&self, key: &str) -> Option<&Data<'a>> {
+        match *self {
+            Data::Hash(ref hash) => hash.get(key),
+            Data::Vector(ref list) => key.parse().ok().and_then(|index: usize| list.get(index)),
+            Data::Lazy(ref cell) => cell.force().get(key),
+            _ => None,
+        }
+    }
+
+    /// A mutable reference to the value under `key`, see `get`
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Data<'a>> {
+        self.unshare_lazy();
+        match *self {
+            Data::Hash(ref mut hash) => hash.get_mut(key),
+            Data::Vector(ref mut list) => {
+                key.parse().ok().and_then(move |index: usize| list.get_mut(index))
+            }
+            _ => None,
+        }
+    }
+
+    /// The value at a dotted path, with each part of the path looked up
+    /// with `get`
+    ///
+    /// ```rust
+    /// use rustache::{Data, HashBuilder, VecBuilder};
+    /// let data = Data::from(HashBuilder::new()
+    ///     .insert("heroes", VecBuilder::new()
+    ///         .push(HashBuilder::new().insert("name", "Anduin"))));
+    ///
+    /// assert_eq!(Some(&Data::from("Anduin")), data.get_path("heroes.0.name"));
+    /// ```
+    pub fn get_path(&self, path: &str) -> Option<&Data<'a>> {
+        path.split('.').try_fold(self, |data, key| data.get(key))
+    }
+
+    /// A mutable reference to the value at a dotted path, see `get_path`
+    pub fn get_path_mut(&mut self, path: &str) -> Option<&mut Data<'a>> {
+        path.split('.').try_fold(self, |data, key| data.get_mut(key))
+    }
+
+    /// Remove and return the value under `key` in a hash, or at the
+    /// position `key` in a vector
+    pub fn remove(&mut self, key: &str) -> Option<Data<'a>> {
+        self.unshare_lazy();
+        match *self {
+            Data::Hash(ref mut hash) => hash.shift_remove(key),
+            Data::Vector(ref mut list) => {
+                match key.parse() {
+                    Ok(index) if index < list.len() => Some(list.remove(index)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    // swaps lazy data for a copy of its value, which can be changed
+    // without touching the copies that share the lazy value
+    fn unshare_lazy(&mut self) {
+        while let Data::Lazy(ref cell) = *self {
+            let value = cell.force().clone();
+            *self = value;
+        }
+    }
+}
+
+// collecting values builds a vector
+impl<'a, V: Into<Data<'a>>> FromIterator<V> for Data<'a> {
+    fn from_iter<I: IntoIterator<Item = V>>(iter: I) -> Data<'a> {
+        Data::Vector(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+// collecting key and value pairs builds a hash
+impl<'a, K: Into<String>, V: Into<Data<'a>>> FromIterator<(K, V)> for Data<'a> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Data<'a> {
+        let hash: Hash<'a> = iter.into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .collect();
+        Data::Hash(hash)
+    }
+}
+
+/// Extending a vector pushes the values onto it, and null data starts out
+/// as an empty vector.
+///
+/// # Panics
+///
+/// Panics if the data is neither a vector nor null.
+impl<'a, V: Into<Data<'a>>> Extend<V> for Data<'a> {
+    fn extend<I: IntoIterator<Item = V>>(&mut self, iter: I) {
+        self.unshare_lazy();
+        if let Data::Null = *self {
+            *self = Data::Vector(Vec::new());
+        }
+        match *self {
+            Data::Vector(ref mut list) => list.extend(iter.into_iter().map(Into::into)),
+            ref other => panic!("can't extend {:?} with values, only a vector", other),
+        }
+    }
+}
+
+/// Extending a hash sets each key to its value, and null data starts out
+/// as an empty hash.
+///
+/// # Panics
+///
+/// Panics if the data is neither a hash nor null.
+impl<'a, K: Into<String>, V: Into<Data<'a>>> Extend<(K, V)> for Data<'a> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        self.unshare_lazy();
+        if let Data::Null = *self {
+            *self = Data::Hash(Hash::new());
+        }
+        match *self {
+            Data::Hash(ref mut hash) => {
+                hash.extend(iter.into_iter().map(|(key, value)| (key.into(), value.into())))
+            }
+            ref other => panic!("can't extend {:?} with key and value pairs, only a hash", other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod query_tests {
+    use {Data, HashBuilder, VecBuilder};
+
+    fn heroes<'a>() -> Data<'a> {
+        Data::from(HashBuilder::new()
+            .insert("heroes", VecBuilder::new()
+                .push(HashBuilder::new().insert("name", "Anduin"))
+                .push(HashBuilder::new().insert("name", "Jaina")))
+            .insert_lazy("deck", || HashBuilder::new().insert("size", 30)))
+    }
+
+    #[test]
+    fn test_get_path() {
+        let data = heroes();
+
+        assert_eq!(Some(&Data::from("Jaina")), data.get_path("heroes.1.name"));
+        assert_eq!(Some(&Data::from(30)), data.get_path("deck.size"));
+        assert_eq!(None, data.get_path("heroes.2.name"));
+        assert_eq!(None, data.get_path("heroes.first"));
+        assert_eq!(None, data.get_path("heroes.0.name.more"));
+    }
+
+    #[test]
+    fn test_get_path_mut() {
+        let mut data = heroes();
+        *data.get_path_mut("heroes.0.name").unwrap() = Data::from("Varian");
+        *data.get_path_mut("deck.size").unwrap() = Data::from(40);
+
+        assert_eq!(Some(&Data::from("Varian")), data.get_path("heroes.0.name"));
+        assert_eq!(Some(&Data::from(40)), data.get_path("deck.size"));
+    }
+
+    #[test]
+    fn test_extend_vector() {
+        let mut data: Data = vec!["Anduin"].into_iter().collect();
+        data.extend(vec!["Jaina", "Thrall"]);
+
+        assert_eq!(Some(&Data::from("Thrall")), data.get("2"));
+
+        let mut empty = Data::Null;
+        empty.extend(vec![1, 2]);
+        assert_eq!(Data::from(vec![Data::from(1), Data::from(2)]), empty);
+    }
+
+    #[test]
+    fn test_extend_hash() {
+        let mut data = heroes();
+        data.extend(vec![("mana", 10), ("deck", 20)]);
+
+        assert_eq!(Some(&Data::from(10)), data.get("mana"));
+        assert_eq!(Some(&Data::from(20)), data.get("deck"));
+        assert_eq!(Some(&Data::from("Jaina")), data.get_path("heroes.1.name"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_extend_scalar() {
+        let mut data = Data::from("Anduin");
+        data.extend(vec![("name", "Jaina")]);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut data = heroes();
+
+        assert_eq!(Some(Data::from("Anduin")),
+                   data.get_path_mut("heroes.0").unwrap().remove("name"));
+        assert!(data.get_path_mut("heroes").unwrap().remove("1").is_some());
+        assert_eq!(None, data.get_path_mut("heroes").unwrap().remove("1"));
+        assert_eq!(None, data.remove("missing"));
+    }
+
+    #[test]
+    fn test_collect() {
+        let list: Data = vec!["Mage", "Druid"].into_iter().collect();
+        let hash: Data = vec![("class", "Mage")].into_iter().collect();
+
+        assert_eq!(Data::from(VecBuilder::new().push("Mage").push("Druid")), list);
+        assert_eq!(Data::from(HashBuilder::new().insert("class", "Mage")), hash);
+    }
+}
@@ -0,0 +1,368 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Arc;
+
+use context::Context;
+use filters;
+use rustache::Render;
+use template::LambdaContext;
+use {ContextLambda, Data, Filter, TruthyFn};
+use errors::*;
+
+/// `Renderer` holds the options a template is rendered with, such as the
+/// syntax extensions it allows.  A renderer with no options renders by
+/// the Mustache spec, the same as `Render::render`.
+///
+/// Filters are an extension that pipes a value through named functions
+/// before it is written out, as in `{{ name | upper | truncate:20 }}`.
+/// The standard filters are always registered, see `enable_filters`.
+/// Helpers are lambdas registered by name, for data that has none, see
+/// `helper`.  Which data hides a section can be changed, see `truthiness`.
+///
+/// ```rust
+/// use rustache::{HashBuilder, Renderer};
+/// use std::io::Cursor;
+///
+/// let data = HashBuilder::new().insert("name", "  Anduin Wrynn ");
+/// let renderer = Renderer::new().enable_filters();
+///
+/// let mut out = Cursor::new(Vec::new());
+/// renderer.render(&data, "{{ name | trim | upper | truncate:6 }}", &mut out).unwrap();
+///
+/// assert_eq!("ANDUIN...", String::from_utf8(out.into_inner()).unwrap());
+/// ```
+#[derive(Clone, Default)]
+pub struct Renderer {
+    filters: HashMap<String, Filter>,
+    use_filters: bool,
+    helpers: HashMap<String, Data<'static>>,
+    use_loop_variables: bool,
+    use_hash_iteration: bool,
+    use_context_paths: bool,
+    truthiness: Truthiness,
+}
+
+/// The rule a `Renderer` uses to decide whether a section's data is true,
+/// which renders a section and hides an inverted section
+#[derive(Clone, Default)]
+pub enum Truthiness {
+    /// Missing data, `false`, null and empty lists are false, as the
+    /// Mustache spec asks.  Everything else is true, see
+    /// `Context::is_truthy`.
+    #[default]
+    Spec,
+    /// As in JavaScript, the empty string, the number `0` and `NaN` are
+    /// false as well.  Hashes are true even when they are empty.
+    JavaScript,
+    /// The function decides for data that is there, and missing data is
+    /// false
+    Custom(TruthyFn),
+}
+
+impl Renderer {
+    /// Create a new `Renderer` that follows the Mustache spec
+    pub fn new() -> Renderer {
+        Renderer {
+            filters: HashMap::new(),
+            use_filters: false,
+            helpers: HashMap::new(),
+            use_loop_variables: false,
+            use_hash_iteration: false,
+            use_context_paths: false,
+            truthiness: Truthiness::Spec,
+        }
+    }
+
+    /// Allow filters in interpolation tags, `{{ value | filter:argument }}`.
+    /// A filter is given the value, or the output of the filter before it,
+    /// and the text after its `:`, which may be quoted to keep spaces.
+    ///
+    /// The standard filters are:
+    ///
+    /// - `upper`, `lower` and `capitalize` change the case of text
+    /// - `trim` removes whitespace from both ends of text
+    /// - `truncate:n` cuts text after `n` characters and adds `...`
+    /// - `default:text` replaces a value that is missing, falsy or empty
+    /// - `join:separator` joins the items of a list, with `, ` by default
+    /// - `length` counts the items of a list or the characters of text
+    /// - `number:n` rounds a number to `n` decimals, 0 by default, and
+    ///   separates thousands with commas
+    /// - `date:format` formats a Unix timestamp or a `YYYY-MM-DD` date,
+    ///   optionally followed by `THH:MM:SS`, with `%Y`, `%y`, `%m`, `%d`,
+    ///   `%e`, `%B`, `%b`, `%H`, `%M`, `%S` and `%%`.  The format is
+    ///   `%Y-%m-%d` by default.
+    ///
+    /// Without this, a tag such as `{{ a | b }}` looks up the name `a | b`,
+    /// as the spec asks.
+    pub fn enable_filters(mut self) -> Renderer {
+        self.use_filters = true;
+        self
+    }
+
+    /// Register a filter, which replaces a standard filter of the same
+    /// name.  The filter is given the value, which is `None` when it is
+    /// missing, and the filter's argument if it has one.
+    ///
+    /// ```rust
+    /// use rustache::{Context, HashBuilder, Renderer};
+    /// use std::io::Cursor;
+    ///
+    /// let data = HashBuilder::new().insert("name", "Anduin");
+    /// let renderer = Renderer::new()
+    ///     .enable_filters()
+    ///     .filter("reverse", |value: Option<&dyn Context>, _: Option<&str>| {
+    ///         let text = value.and_then(|value| value.interpolate()).unwrap_or_default();
+    ///         text.chars().rev().collect::<String>()
+    ///     });
+    ///
+    /// let mut out = Cursor::new(Vec::new());
+    /// renderer.render(&data, "{{ name | reverse }}", &mut out).unwrap();
+    ///
+    /// assert_eq!("niudnA", String::from_utf8(out.into_inner()).unwrap());
+    /// ```
+    pub fn filter<K, F, V>(mut self, name: K, f: F) -> Renderer
+        where K: ToString,
+              F: Fn(Option<&dyn Context>, Option<&str>) -> V + Send + Sync + 'static,
+              V: Into<Data<'static>>
+    {
+        self.filters.insert(name.to_string(), Arc::new(move |value, arg| f(value, arg).into()));
+        self
+    }
+
+    /// Register a helper, which a tag uses when no data is found for its
+    /// name.  Helpers work like context lambdas, see
+    /// `HashBuilder::insert_context_lambda`: a section's helper is given
+    /// the raw text of the section, an interpolation's is given no text,
+    /// and what the helper returns is written out.  Data that can't hold
+    /// lambdas, such as JSON, can use helpers instead.
+    ///
+    /// ```rust
+    /// use rustache::{Renderer, LambdaContext};
+    /// use rustc_serialize::json::Json;
+    /// use std::io::Cursor;
+    /// # extern crate rustache;
+    /// # extern crate rustc_serialize;
+    /// # fn main() {
+    ///
+    /// let data = Json::from_str(r#"{"name": "Anduin"}"#).unwrap();
+    /// let renderer = Renderer::new()
+    ///     .helper("bold", |text: &str, context: &mut LambdaContext| {
+    ///         format!("<b>{}</b>", context.render(text).unwrap())
+    ///     })
+    ///     .helper("year", |_: &str, _: &mut LambdaContext| "2014".to_string());
+    ///
+    /// let mut out = Cursor::new(Vec::new());
+    /// renderer.render(&data, "{{#bold}}{{name}}{{/bold}} {{year}}", &mut out).unwrap();
+    ///
+    /// assert_eq!("<b>Anduin</b> 2014", String::from_utf8(out.into_inner()).unwrap());
+    /// # }
+    /// ```
+    pub fn helper<K, F>(mut self, name: K, f: F) -> Renderer
+        where K: ToString,
+              F: Fn(&str, &mut LambdaContext) -> String + Send + Sync + 'static
+    {
+        let f: ContextLambda<'static> = Arc::new(f);
+        self.helpers.insert(name.to_string(), Data::from(f));
+        self
+    }
+
+    /// Give the items of a list their place in it while a section renders
+    /// them.  Each item has the names `@index`, counting from 0, `@index1`,
+    /// counting from 1, `@first`, `@last` and `@length`.  The items of a
+    /// stream are all read before the first is rendered, to count them.
+    ///
+    /// ```rust
+    /// use rustache::{HashBuilder, Renderer, VecBuilder};
+    /// use std::io::Cursor;
+    ///
+    /// let data = HashBuilder::new()
+    ///     .insert("classes", VecBuilder::new()
+    ///         .push(HashBuilder::new().insert("name", "Mage"))
+    ///         .push(HashBuilder::new().insert("name", "Druid")));
+    /// let renderer = Renderer::new().enable_loop_variables();
+    ///
+    /// let mut out = Cursor::new(Vec::new());
+    /// renderer.render(&data, "{{#classes}}{{@index1}}. {{name}}{{^@last}}, {{/@last}}{{/classes}}",
+    ///                 &mut out).unwrap();
+    ///
+    /// assert_eq!("1. Mage, 2. Druid", String::from_utf8(out.into_inner()).unwrap());
+    /// ```
+    pub fn enable_loop_variables(mut self) -> Renderer {
+        self.use_loop_variables = true;
+        self
+    }
+
+    /// Allow a section to render once for each entry of a hash, by adding
+    /// `.@entries` to the hash's name.  Each entry has the names `@key` and
+    /// `@value`, and the names of its value when the value is a hash.
+    /// Entries come in the order `Context::entries` gives them, which for
+    /// data built with a `HashBuilder` is the order the keys were inserted.
+    ///
+    /// ```rust
+    /// use rustache::{HashBuilder, Renderer};
+    /// use std::io::Cursor;
+    ///
+    /// let data = HashBuilder::new()
+    ///     .insert("settings", HashBuilder::new()
+    ///         .insert("volume", 11)
+    ///         .insert("difficulty", "heroic"));
+    /// let renderer = Renderer::new().enable_hash_iteration();
+    ///
+    /// let mut out = Cursor::new(Vec::new());
+    /// renderer.render(&data, "{{#settings.@entries}}{{@key}}={{@value}};{{/settings.@entries}}",
+    ///                 &mut out).unwrap();
+    ///
+    /// assert_eq!("volume=11;difficulty=heroic;", String::from_utf8(out.into_inner()).unwrap());
+    /// ```
+    pub fn enable_hash_iteration(mut self) -> Renderer {
+        self.use_hash_iteration = true;
+        self
+    }
+
+    /// Allow names to start from an outer context, instead of the
+    /// innermost context that has them.  `../name` looks `name` up from
+    /// the context around the current one, and each further `../` goes out
+    /// one more.  `@root.name` looks `name` up in the data the template was
+    /// rendered with.  Each item of a list and each section over a hash is
+    /// a context, and the rest of the name may be dotted as usual.
+    ///
+    /// ```rust
+    /// use rustache::{HashBuilder, Renderer, VecBuilder};
+    /// use std::io::Cursor;
+    ///
+    /// let data = HashBuilder::new()
+    ///     .insert("currency", "USD")
+    ///     .insert("orders", VecBuilder::new()
+    ///         .push(HashBuilder::new()
+    ///             .insert("currency", "EUR")
+    ///             .insert("items", VecBuilder::new()
+    ///                 .push(HashBuilder::new().insert("price", 5).insert("currency", "?")))));
+    /// let renderer = Renderer::new().enable_context_paths();
+    ///
+    /// let mut out = Cursor::new(Vec::new());
+    /// renderer.render(&data,
+    ///                 "{{#orders}}{{#items}}{{price}} {{../currency}} \
+    ///                  ({{@root.currency}}){{/items}}{{/orders}}",
+    ///                 &mut out).unwrap();
+    ///
+    /// assert_eq!("5 EUR (USD)", String::from_utf8(out.into_inner()).unwrap());
+    /// ```
+    pub fn enable_context_paths(mut self) -> Renderer {
+        self.use_context_paths = true;
+        self
+    }
+
+    /// Choose the rule that decides whether a section's data is true.
+    /// Sections render by the spec unless this is changed.
+    ///
+    /// ```rust
+    /// use rustache::{HashBuilder, Renderer, Truthiness};
+    /// use std::io::Cursor;
+    ///
+    /// let data = HashBuilder::new()
+    ///     .insert("unread", 0)
+    ///     .insert("motto", "");
+    /// let template = "{{#unread}}{{unread}} new{{/unread}}{{^motto}}no motto{{/motto}}";
+    ///
+    /// let mut out = Cursor::new(Vec::new());
+    /// Renderer::new().render(&data, template, &mut out).unwrap();
+    /// assert_eq!("0 new", String::from_utf8(out.into_inner()).unwrap());
+    ///
+    /// let mut out = Cursor::new(Vec::new());
+    /// Renderer::new().truthiness(Truthiness::JavaScript).render(&data, template, &mut out).unwrap();
+    /// assert_eq!("no motto", String::from_utf8(out.into_inner()).unwrap());
+    /// ```
+    pub fn truthiness(mut self, rule: Truthiness) -> Renderer {
+        self.truthiness = rule;
+        self
+    }
+
+    /// Decide whether a section's data is true with a function, which is
+    /// only called for data that is there, see `Truthiness::Custom`
+    ///
+    /// ```rust
+    /// use rustache::{Context, HashBuilder, Renderer};
+    /// use std::io::Cursor;
+    ///
+    /// let data = HashBuilder::new().insert("status", "none");
+    /// let renderer = Renderer::new()
+    ///     .truthy_when(|data: &dyn Context| {
+    ///         data.is_truthy() && data.interpolate().map_or(true, |text| text != "none")
+    ///     });
+    ///
+    /// let mut out = Cursor::new(Vec::new());
+    /// renderer.render(&data, "{{^status}}idle{{/status}}", &mut out).unwrap();
+    ///
+    /// assert_eq!("idle", String::from_utf8(out.into_inner()).unwrap());
+    /// ```
+    pub fn truthy_when<F>(self, f: F) -> Renderer
+        where F: Fn(&dyn Context) -> bool + Send + Sync + 'static
+    {
+        self.truthiness(Truthiness::Custom(Arc::new(f)))
+    }
+
+    /// Render a template with the given data
+    pub fn render<R, W>(&self, data: &R, template: &str, writer: &mut W) -> Result<()>
+        where R: Render + ?Sized,
+              W: Write
+    {
+        data.render_with(self, template, writer)
+    }
+
+    // whether tags may pipe their values through filters
+    #[doc(hidden)]
+    pub fn use_filters(&self) -> bool {
+        self.use_filters
+    }
+
+    // the filter registered under `name`, or the standard filter
+    #[doc(hidden)]
+    pub fn find_filter(&self, name: &str) -> Option<Filter> {
+        self.filters.get(name).cloned().or_else(|| filters::standard(name))
+    }
+
+    // whether the items of a list know their place in it
+    #[doc(hidden)]
+    pub fn use_loop_variables(&self) -> bool {
+        self.use_loop_variables
+    }
+
+    // whether sections can iterate the entries of a hash
+    #[doc(hidden)]
+    pub fn use_hash_iteration(&self) -> bool {
+        self.use_hash_iteration
+    }
+
+    // whether names can start from an outer context
+    #[doc(hidden)]
+    pub fn use_context_paths(&self) -> bool {
+        self.use_context_paths
+    }
+
+    // whether a section's data is true by the renderer's rule
+    #[doc(hidden)]
+    pub fn is_truthy(&self, data: &dyn Context) -> bool {
+        match self.truthiness {
+            Truthiness::Spec => data.is_truthy(),
+            Truthiness::JavaScript => {
+                if !data.is_truthy() {
+                    return false;
+                }
+                match data.interpolate() {
+                    Some(ref text) if data.is_number() => {
+                        text.parse::<f64>().map_or(true, |num| num != 0.0 && !num.is_nan())
+                    }
+                    Some(text) => !text.is_empty(),
+                    None => true,
+                }
+            }
+            Truthiness::Custom(ref f) => f(data),
+        }
+    }
+
+    // the helper registered under `name`
+    #[doc(hidden)]
+    pub fn find_helper(&self, name: &str) -> Option<&dyn Context> {
+        self.helpers.get(name).map(|helper| helper as &dyn Context)
+    }
+}
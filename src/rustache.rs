@@ -1,134 +1,220 @@
 use std::io::Write;
+use indexmap::IndexMap;
 use compiler;
 use parser;
 use rustc_serialize::json::Json;
 use rustc_serialize::json::Json::{Boolean, Null, I64, U64, F64, Array, Object};
 use rustc_serialize::json::Json::String as JString;
-use build::{HashBuilder, VecBuilder};
+use build::HashBuilder;
+use layers::Layers;
+use renderer::Renderer;
+use Data;
+#[cfg(feature = "serde_json")]
+use serde_json;
 use template::Template;
+use context::Context;
 use errors::*;
 
 /// Defines a `renderable` trait, so that all of our data is renderable
 pub trait Render {
     /// `render` function on a `renderable` returns a `reader`
     fn render<W: Write>(&self, template: &str, writer: &mut W) -> Result<()>;
+
+    /// Render with the options of a `Renderer`, see `Renderer::render`.
+    /// By default the options are ignored and this is the same as
+    /// `render`, so only types that support them need to implement it.
+    fn render_with<W: Write>(&self, _renderer: &Renderer, template: &str, writer: &mut W) -> Result<()> {
+        self.render(template, writer)
+    }
 }
 
 /// Implement the `renderable` trait on the `HashBuilder` type
 impl<'a> Render for HashBuilder<'a> {
     fn render<W: Write>(&self, template: &str, writer: &mut W) -> Result<()> {
+        self.render_with(&Renderer::new(), template, writer)
+    }
+
+    fn render_with<W: Write>(&self, renderer: &Renderer, template: &str, writer: &mut W) -> Result<()> {
         // Create our nodes
         let tokens = compiler::create_tokens(template);
         let nodes = parser::parse_nodes(&tokens);
 
         // Render and write out
-        Template::new().render_data(writer, self, &nodes)
+        Template::new(renderer).render_data(writer, self, &nodes)
+    }
+}
+
+/// Implement the `renderable` trait on `Layers`, looking names up through
+/// each layer
+impl<'a> Render for Layers<'a> {
+    fn render<W: Write>(&self, template: &str, writer: &mut W) -> Result<()> {
+        self.render_with(&Renderer::new(), template, writer)
+    }
+
+    fn render_with<W: Write>(&self, renderer: &Renderer, template: &str, writer: &mut W) -> Result<()> {
+        let tokens = compiler::create_tokens(template);
+        let nodes = parser::parse_nodes(&tokens);
+
+        Template::new(renderer).render_layers(writer, self, &nodes)
+    }
+}
+
+/// Implement the `renderable` trait on `Data`
+impl<'a> Render for Data<'a> {
+    fn render<W: Write>(&self, template: &str, writer: &mut W) -> Result<()> {
+        self.render_with(&Renderer::new(), template, writer)
+    }
+
+    fn render_with<W: Write>(&self, renderer: &Renderer, template: &str, writer: &mut W) -> Result<()> {
+        let tokens = compiler::create_tokens(template);
+        let nodes = parser::parse_nodes(&tokens);
+
+        Template::new(renderer).render(writer, self, &nodes)
+    }
+}
+
+/// Implement the `renderable` trait on any `Context`, which is rendered
+/// in place
+impl<'a> Render for dyn Context + 'a {
+    fn render<W: Write>(&self, template: &str, writer: &mut W) -> Result<()> {
+        self.render_with(&Renderer::new(), template, writer)
+    }
+
+    fn render_with<W: Write>(&self, renderer: &Renderer, template: &str, writer: &mut W) -> Result<()> {
+        let tokens = compiler::create_tokens(template);
+        let nodes = parser::parse_nodes(&tokens);
+
+        Template::new(renderer).render(writer, self, &nodes)
     }
 }
 
+/// Implement the `renderable` trait on the serde JSON type, which is
+/// rendered in place and doesn't need to be an object: `{{.}}` is the
+/// value itself, so `{{#.}}{{.}}{{/.}}` renders the items of an array
+#[cfg(feature = "serde_json")]
+impl Render for serde_json::Value {
+    fn render<W: Write>(&self, template: &str, writer: &mut W) -> Result<()> {
+        self.render_with(&Renderer::new(), template, writer)
+    }
+
+    fn render_with<W: Write>(&self, renderer: &Renderer, template: &str, writer: &mut W) -> Result<()> {
+        let tokens = compiler::create_tokens(template);
+        let nodes = parser::parse_nodes(&tokens);
+
+        Template::new(renderer).render(writer, self, &nodes)
+    }
+}
 
-/// Implement the `renderable` trait on the JSON type
+/// Implement the `renderable` trait on the JSON type, which doesn't need
+/// to be an object: `{{.}}` is the value itself
 impl Render for Json {
     fn render<W: Write>(&self, template: &str, writer: &mut W) -> Result<()> {
-        parse_json(self).render(template, writer)
+        self.render_with(&Renderer::new(), template, writer)
+    }
+
+    fn render_with<W: Write>(&self, renderer: &Renderer, template: &str, writer: &mut W) -> Result<()> {
+        try!(parse_json(self)).render_with(renderer, template, writer)
     }
 }
 
 impl Render for ToString {
     fn render<W: Write>(&self, template: &str, writer: &mut W) -> Result<()> {
+        self.render_with(&Renderer::new(), template, writer)
+    }
+
+    fn render_with<W: Write>(&self, renderer: &Renderer, template: &str, writer: &mut W) -> Result<()> {
 
         let json = match Json::from_str(&self.to_string()) {
             Ok(json) => json,
             Err(err) => return Err(err.into()),
         };
 
-        parse_json(&json).render(template, writer)
+        try!(parse_json(&json)).render_with(renderer, template, writer)
     }
 }
 
-// parses a Rust JSON hash and matches all possible types that may be passed in
-// returning a HashBuilder
-fn parse_json(json: &Json) -> HashBuilder {
-    let mut data = HashBuilder::new();
-    for (k, v) in json.as_object().unwrap().iter() {
-        match *v {
-            I64(num) => {
-                data = data.insert(&k[..], num.to_string());
-            }
-            U64(num) => {
-                data = data.insert(&k[..], num.to_string());
-            }
-            F64(num) => {
-                data = data.insert(&k[..], num.to_string());
+// parses any Rust JSON value, matching all possible types that may be
+// passed in and returning the equivalent Data
+fn parse_json<'a>(json: &Json) -> Result<Data<'a>> {
+    let data = match *json {
+        I64(num) => Data::from(num),
+        U64(num) => Data::from(num),
+        F64(num) => {
+            // NaN and infinity have no JSON representation, so they can
+            // only show up in hand built values
+            if !num.is_finite() {
+                return Err(ErrorKind::UnexpectedDataType(num.to_string()).into());
             }
-            Boolean(val) => {
-                data = data.insert(&k[..], val);
-            }
-            Array(ref list) => {
-                let mut builder = VecBuilder::new();
-                for item in list.iter() {
-                    builder = match *item {
-                        Object(_) => builder.push(parse_json(item)),
-                        Array(_) => builder.push(parse_json_vector(item)),
-                        JString(_) => builder.push(item.as_string().unwrap()),
-                        Boolean(_) => builder.push(item.as_boolean().unwrap()),
-                        _ => builder,
-                    }
-                }
-                data = data.insert(&k[..], builder);
-            }
-            Object(_) => {
-                data = data.insert(&k[..], parse_json(v));
+            Data::Float(num)
+        }
+        Boolean(val) => Data::Bool(val),
+        JString(ref text) => Data::String(text.clone()),
+        Null => Data::Null,
+        Array(ref list) => {
+            let mut data = Vec::with_capacity(list.len());
+            for item in list.iter() {
+                data.push(try!(parse_json(item)));
             }
-            Null => {}
-            JString(ref text) => {
-                data = data.insert(&k[..], &text[..]);
+            Data::Vector(data)
+        }
+        Object(ref hash) => {
+            let mut data = IndexMap::with_capacity(hash.len());
+            for (k, v) in hash.iter() {
+                data.insert(k.clone(), try!(parse_json(v)));
             }
+            Data::Hash(data)
         }
-    }
+    };
 
-    data
+    Ok(data)
 }
 
-// parses a Rust JSON vector and matches all possible types that may be passed in
-// returning a VecBuider
-fn parse_json_vector(json: &Json) -> VecBuilder {
-    let mut data = VecBuilder::new();
-    for v in json.as_array().unwrap().iter() {
-        match *v {
-            I64(num) => {
-                data = data.push(num.to_string());
-            }
-            U64(num) => {
-                data = data.push(num.to_string());
-            }
-            F64(num) => {
-                data = data.push(num.to_string());
-            }
-            Boolean(val) => {
-                data = data.push(val);
-            }
-            Array(ref list) => {
-                let mut builder = VecBuilder::new();
-                for item in list.iter() {
-                    builder = match *item {
-                        Object(_) => builder.push(parse_json(item)),
-                        Array(_) => builder.push(parse_json_vector(item)),
-                        JString(_) => builder.push(item.as_string().unwrap()),
-                        Boolean(_) => builder.push(item.as_boolean().unwrap()),
-                        _ => builder,
-                    }
-                }
-                data = data.push(builder);
-            }
-            Object(_) => {
-                data = data.push(parse_json(v));
-            }
-            Null => {}
-            JString(ref text) => {
-                data = data.push(&text[..]);
-            }
-        }
+#[cfg(test)]
+mod rustache_tests {
+    use indexmap::IndexMap;
+    use rustc_serialize::json::Json;
+
+    use rustache::parse_json;
+    use Data;
+
+    #[test]
+    fn test_parse_json_keeps_numbers() {
+        let json = Json::from_str("[1, -2, 2.5, 4294967296]").unwrap();
+
+        assert_eq!(Data::Vector(vec![Data::Integer(1),
+                                     Data::Integer(-2),
+                                     Data::Float(2.5),
+                                     Data::Integer(4294967296)]),
+                   parse_json(&json).unwrap());
+    }
+
+    #[test]
+    fn test_parse_json_wide_unsigned() {
+        let json = Json::from_str("18446744073709551615").unwrap();
+
+        assert_eq!(Data::Unsigned(18446744073709551615), parse_json(&json).unwrap());
+    }
+
+    #[test]
+    fn test_parse_json_keeps_null() {
+        let json = Json::from_str(r#"{"name": null, "list": [null, true]}"#).unwrap();
+        let mut hash = IndexMap::new();
+        hash.insert("name".to_string(), Data::Null);
+        hash.insert("list".to_string(),
+                    Data::Vector(vec![Data::Null, Data::Bool(true)]));
+
+        assert_eq!(Data::Hash(hash), parse_json(&json).unwrap());
+    }
+
+    #[test]
+    fn test_parse_json_scalar_root() {
+        let json = Json::from_str(r#""text""#).unwrap();
+
+        assert_eq!(Data::String("text".to_string()), parse_json(&json).unwrap());
+    }
+
+    #[test]
+    fn test_parse_json_rejects_nan() {
+        assert!(parse_json(&Json::F64(f64::NAN)).is_err());
     }
-    data
 }
@@ -0,0 +1,371 @@
+use std::fmt::Display;
+use std::marker::PhantomData;
+
+use serde::ser::{self, Serialize};
+
+use Data;
+use build::{HashBuilder, VecBuilder};
+use errors::*;
+
+/// Convert any `serde::Serialize` value into `Data`
+///
+/// Structs and maps become hashes, sequences and tuples become vectors and
+/// `None` becomes null.  Enums follow serde's externally tagged layout.
+///
+/// ```rust
+/// # extern crate rustache;
+/// # use std::collections::BTreeMap;
+/// # fn main() {
+/// use rustache::Render;
+/// use std::io::Cursor;
+///
+/// let mut hero = BTreeMap::new();
+/// hero.insert("name", "Anduin");
+///
+/// let mut out = Cursor::new(Vec::new());
+/// rustache::to_data(&hero).unwrap().render("{{ name }}", &mut out).unwrap();
+/// # }
+/// ```
+pub fn to_data<'a, T: ?Sized + Serialize>(value: &T) -> Result<Data<'a>> {
+    value.serialize(Serializer::new())
+}
+
+impl ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Error {
+        ErrorKind::Msg(msg.to_string()).into()
+    }
+}
+
+// Serializes a value into `Data`
+struct Serializer<'a> {
+    marker: PhantomData<Data<'a>>,
+}
+
+impl<'a> Serializer<'a> {
+    fn new() -> Serializer<'a> {
+        Serializer { marker: PhantomData }
+    }
+}
+
+impl<'a> ser::Serializer for Serializer<'a> {
+    type Ok = Data<'a>;
+    type Error = Error;
+
+    type SerializeSeq = SerializeVec<'a>;
+    type SerializeTuple = SerializeVec<'a>;
+    type SerializeTupleStruct = SerializeVec<'a>;
+    type SerializeTupleVariant = SerializeVec<'a>;
+    type SerializeMap = SerializeHash<'a>;
+    type SerializeStruct = SerializeHash<'a>;
+    type SerializeStructVariant = SerializeHash<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<Data<'a>> {
+        Ok(Data::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Data<'a>> {
+        Ok(Data::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Data<'a>> {
+        Ok(Data::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Data<'a>> {
+        Ok(Data::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Data<'a>> {
+        Ok(Data::from(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Data<'a>> {
+        Ok(Data::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Data<'a>> {
+        Ok(Data::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Data<'a>> {
+        Ok(Data::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Data<'a>> {
+        Ok(Data::from(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Data<'a>> {
+        Ok(Data::Float(v as f64))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Data<'a>> {
+        Ok(Data::Float(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Data<'a>> {
+        Ok(Data::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Data<'a>> {
+        Ok(Data::String(v.to_owned()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Data<'a>> {
+        Ok(Data::Vector(v.iter().map(|&b| Data::from(b)).collect()))
+    }
+
+    fn serialize_none(self) -> Result<Data<'a>> {
+        Ok(Data::Null)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Data<'a>> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Data<'a>> {
+        Ok(Data::Null)
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Data<'a>> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(self,
+                              _: &'static str,
+                              _: u32,
+                              variant: &'static str)
+                              -> Result<Data<'a>> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self,
+                                                       _: &'static str,
+                                                       value: &T)
+                                                       -> Result<Data<'a>> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(self,
+                                                        _: &'static str,
+                                                        _: u32,
+                                                        variant: &'static str,
+                                                        value: &T)
+                                                        -> Result<Data<'a>> {
+        let value = try!(to_data(value));
+        Ok(HashBuilder::new().insert(variant, value).into())
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<SerializeVec<'a>> {
+        Ok(SerializeVec {
+            variant: None,
+            data: VecBuilder::new(),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeVec<'a>> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, len: usize) -> Result<SerializeVec<'a>> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(self,
+                               _: &'static str,
+                               _: u32,
+                               variant: &'static str,
+                               _: usize)
+                               -> Result<SerializeVec<'a>> {
+        Ok(SerializeVec {
+            variant: Some(variant),
+            data: VecBuilder::new(),
+        })
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<SerializeHash<'a>> {
+        Ok(SerializeHash {
+            variant: None,
+            key: None,
+            data: HashBuilder::new(),
+        })
+    }
+
+    fn serialize_struct(self, _: &'static str, len: usize) -> Result<SerializeHash<'a>> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(self,
+                                _: &'static str,
+                                _: u32,
+                                variant: &'static str,
+                                _: usize)
+                                -> Result<SerializeHash<'a>> {
+        Ok(SerializeHash {
+            variant: Some(variant),
+            key: None,
+            data: HashBuilder::new(),
+        })
+    }
+}
+
+// Collects sequences, tuples and tuple variants into a vector
+struct SerializeVec<'a> {
+    variant: Option<&'static str>,
+    data: VecBuilder<'a>,
+}
+
+impl<'a> SerializeVec<'a> {
+    fn push<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        let value = try!(to_data(value));
+        let data = ::std::mem::replace(&mut self.data, VecBuilder::new());
+        self.data = data.push(value);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Data<'a>> {
+        match self.variant {
+            Some(variant) => Ok(HashBuilder::new().insert(variant, self.data).into()),
+            None => Ok(self.data.into()),
+        }
+    }
+}
+
+impl<'a> ser::SerializeSeq for SerializeVec<'a> {
+    type Ok = Data<'a>;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Data<'a>> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTuple for SerializeVec<'a> {
+    type Ok = Data<'a>;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Data<'a>> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for SerializeVec<'a> {
+    type Ok = Data<'a>;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Data<'a>> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTupleVariant for SerializeVec<'a> {
+    type Ok = Data<'a>;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Data<'a>> {
+        self.finish()
+    }
+}
+
+// Collects maps, structs and struct variants into a hash
+struct SerializeHash<'a> {
+    variant: Option<&'static str>,
+    key: Option<String>,
+    data: HashBuilder<'a>,
+}
+
+impl<'a> SerializeHash<'a> {
+    fn insert<T: ?Sized + Serialize>(&mut self, key: String, value: &T) -> Result<()> {
+        let value = try!(to_data(value));
+        let data = ::std::mem::replace(&mut self.data, HashBuilder::new());
+        self.data = data.insert(key, value);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Data<'a>> {
+        match self.variant {
+            Some(variant) => Ok(HashBuilder::new().insert(variant, self.data).into()),
+            None => Ok(self.data.into()),
+        }
+    }
+}
+
+impl<'a> ser::SerializeMap for SerializeHash<'a> {
+    type Ok = Data<'a>;
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<()> {
+        // hash keys have to be strings, so only keys that render
+        // as plain values are accepted
+        self.key = match try!(to_data(key)) {
+            Data::String(key) => Some(key),
+            Data::Integer(key) => Some(key.to_string()),
+            Data::Unsigned(key) => Some(key.to_string()),
+            Data::Float(key) => Some(key.to_string()),
+            Data::Bool(key) => Some(key.to_string()),
+            other => {
+                return Err(ErrorKind::UnexpectedDataType(format!("{:?}", other)).into());
+            }
+        };
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        match self.key.take() {
+            Some(key) => self.insert(key, value),
+            None => Err("serialize_value called before serialize_key".into()),
+        }
+    }
+
+    fn end(self) -> Result<Data<'a>> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeStruct for SerializeHash<'a> {
+    type Ok = Data<'a>;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self,
+                                              key: &'static str,
+                                              value: &T)
+                                              -> Result<()> {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Data<'a>> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeStructVariant for SerializeHash<'a> {
+    type Ok = Data<'a>;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self,
+                                              key: &'static str,
+                                              value: &T)
+                                              -> Result<()> {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Data<'a>> {
+        self.finish()
+    }
+}
@@ -7,20 +7,198 @@ use compiler;
 use parser;
 use parser::Node;
 use parser::Node::{Value, Static, Unescaped, Section, Part};
-use Data;
-use Data::{Bool, Integer, Float, Vector, Hash, Lambda};
-use build::HashBuilder;
 use std::collections::HashMap;
 
+use build::HashBuilder;
+use layers::Layers;
+use renderer::Renderer;
+use context::{self, Context, Items};
+use context::sealed::{Internal, Sealed};
+use Data;
+
 use errors::*;
 
-pub struct Template {
+/// What a context lambda is given to render with, see
+/// `HashBuilder::insert_context_lambda`
+///
+/// Text is rendered and names are looked up the same way they would be
+/// in place of the lambda's tag, so inside a section or a list they see
+/// its data.
+pub struct LambdaContext<'r, 'l: 'r> {
+    template: &'r mut Template<'l>,
+    datastore: &'r dyn Context,
+    sections: &'r [String],
+}
+
+impl<'r, 'l> LambdaContext<'r, 'l> {
+    /// Render a template, such as the text of the lambda's section, with
+    /// the data around the lambda
+    pub fn render(&mut self, text: &str) -> Result<String> {
+        let tokens = compiler::create_tokens(text);
+        let nodes = parser::parse_nodes(&tokens);
+        let mut sections = self.sections.to_vec();
+        let mut out = Vec::new();
+
+        try!(self.template.render_section_children(&nodes, self.datastore, &mut sections, &mut out));
+        Ok(String::from_utf8_lossy(&out).into_owned())
+    }
+
+    /// Look up a name, which may be dotted, in the data around the lambda
+    pub fn lookup(&self, name: &str) -> Option<&dyn Context> {
+        self.template.look_up_path(name, self.sections, self.datastore)
+    }
+}
+
+// data rendered inside the data around it, such as an item of a list,
+// where names the data doesn't have are looked up in the data around it
+struct Scope<'b> {
+    data: &'b dyn Context,
+    parent: &'b dyn Context,
+}
+
+impl<'b> Context for Scope<'b> {
+    fn lookup(&self, key: &str) -> Option<&dyn Context> {
+        if parser::is_implicit(key) {
+            return self.data.lookup(key).or(Some(self.data));
+        }
+        self.data.lookup(key).or_else(|| self.parent.lookup(key))
+    }
+
+    fn internal(&self, _sealed: Sealed) -> Option<&dyn Internal> {
+        Some(self)
+    }
+}
+
+impl<'b> Internal for Scope<'b> {
+    fn parent_context(&self) -> Option<&dyn Context> {
+        Some(self.parent)
+    }
+}
+
+// the hash of a section data is rendered in after leaving the section
+// behind, such as the section a list is in for the list's items
+struct SectionScope<'b> {
+    data: &'b dyn Context,
+    parent: Box<dyn Context + 'b>,
+}
+
+impl<'b> Context for SectionScope<'b> {
+    fn lookup(&self, key: &str) -> Option<&dyn Context> {
+        self.data.lookup(key).or_else(|| self.parent.lookup(key))
+    }
+
+    fn internal(&self, _sealed: Sealed) -> Option<&dyn Internal> {
+        Some(self)
+    }
+}
+
+impl<'b> Internal for SectionScope<'b> {
+    fn parent_context(&self) -> Option<&dyn Context> {
+        Some(&*self.parent)
+    }
+}
+
+// an item of a list that knows its place in the list, see
+// `Renderer::enable_loop_variables`
+struct LoopItem<'b> {
+    data: &'b dyn Context,
+    index: usize,
+    index1: usize,
+    first: bool,
+    last: bool,
+    length: usize,
+}
+
+impl<'b> Context for LoopItem<'b> {
+    fn lookup(&self, key: &str) -> Option<&dyn Context> {
+        match key {
+            "@index" => Some(&self.index),
+            "@index1" => Some(&self.index1),
+            "@first" => Some(&self.first),
+            "@last" => Some(&self.last),
+            "@length" => Some(&self.length),
+            "." => Some(self.data),
+            _ => self.data.lookup(key),
+        }
+    }
+}
+
+// looks up the next part of a dotted name in data, where a number picks
+// an item of a list
+fn look_up_part<'b>(data: &'b dyn Context, part: &str) -> Option<&'b dyn Context> {
+    data.lookup(part).or_else(|| {
+        if parser::is_index(part) {
+            part.parse().ok().and_then(|index| data.item(index))
+        } else {
+            None
+        }
+    })
+}
+
+// the suffix of a section that iterates the entries of a hash
+const ENTRIES_SUFFIX: &str = ".@entries";
+
+// an entry of a hash, see `Renderer::enable_hash_iteration`.  names other
+// than `@key` and `@value` are looked up in the value.
+struct HashEntry<'b> {
+    key: &'b str,
+    value: &'b dyn Context,
+}
+
+impl<'b> Context for HashEntry<'b> {
+    fn lookup(&self, key: &str) -> Option<&dyn Context> {
+        match key {
+            "@key" => Some(&self.key),
+            "@value" => Some(&self.value),
+            _ => self.value.lookup(key),
+        }
+    }
+}
+
+pub struct Template<'l> {
     partials_path: String,
+    // the options to render with
+    renderer: &'l Renderer,
+    // the layers of data under the data being rendered, most
+    // general first
+    layers: &'l [Box<dyn Context + 'l>],
 }
 
-impl Template {
-    pub fn new() -> Template {
-        Template { partials_path: String::new() }
+impl<'l> Template<'l> {
+    pub fn new(renderer: &'l Renderer) -> Template<'l> {
+        Template {
+            partials_path: String::new(),
+            renderer,
+            layers: &[],
+        }
+    }
+
+    // looks key up in the data, and when it isn't there, in the layers
+    // under the data, starting with the most specific.  this is done for
+    // every name, so names in the layers can be used anywhere, even in
+    // the items of a list.  names found nowhere in the data may name one
+    // of the renderer's helpers.  `.` is the data itself, so data that
+    // isn't a hash, such as a list at the root, can still be rendered.
+    fn lookup<'b>(&self, datastore: &'b dyn Context, key: &str) -> Option<&'b dyn Context>
+        where 'l: 'b
+    {
+        if parser::is_path(key) {
+            return self.look_up_path(key, &[], datastore);
+        }
+        if parser::is_implicit(key) {
+            return datastore.lookup(key).or(Some(datastore));
+        }
+
+        let layers = self.layers;
+        let renderer = self.renderer;
+        datastore.lookup(key)
+            .or_else(|| {
+                layers.iter()
+                    .rev()
+                    .filter_map(|layer| layer.lookup(key))
+                    .next()
+            })
+            .or_else(|| renderer.find_helper(key))
     }
 
     // utility method to write out rendered template with error handling
@@ -57,6 +235,21 @@ impl Template {
         }).collect()
     }
 
+    // a data lambda is called when its tag is rendered, and what it returns
+    // is used in its place, like any other data for the tag
+    fn call_data_lambda<'b>(&mut self,
+                            data: Option<&'b dyn Context>,
+                            datastore: &'b dyn Context,
+                            sections: &[String])
+                            -> Option<Box<dyn Context + 'b>> {
+        let mut context = LambdaContext {
+            template: self,
+            datastore,
+            sections,
+        };
+        data.and_then(|data| data.call_data_lambda(&mut context))
+    }
+
     // key:       the key we're looking for
     // sections:  an array of the nested sections to look through, e.g. [e, d, c, b, a]
     // datastore: the hash of the data to search for key in
@@ -64,11 +257,26 @@ impl Template {
     // TODO: handle vector data for real, change to not build vector, but
     // iterate the same way until data is found
     //
-    fn look_up_section_data<'a, 'b>(&self,
-                                    key: &str,
-                                    sections: &[String],
-                                    datastore: &'b HashMap<String, Data<'a>>)
-                                    -> Option<&'b Data<'a>> {
+    fn look_up_section_data<'b>(&self,
+                                key: &str,
+                                sections: &[String],
+                                datastore: &'b dyn Context)
+                                -> Option<&'b dyn Context>
+        where 'l: 'b
+    {
+        if parser::is_path(key) {
+            return self.look_up_path(key, sections, datastore);
+        }
+
+        // `.` is the data of the section we're in, or the data itself
+        // outside of any
+        if parser::is_implicit(key) {
+            return match sections.split_last() {
+                Some((section, outer)) => self.look_up_section_data(section, outer, datastore),
+                None => self.lookup(datastore, key),
+            };
+        }
+
         let mut hashes = Vec::new();
         let mut hash = datastore;
 
@@ -90,10 +298,10 @@ impl Template {
         //                           {"value": "foo", "c": { "cdata": foo }},
         //                           { b: { "value": "foo", c: {"cdata": foo}}]
         for section in sections.iter() {
-            if let Some(data) = hash.get(section) {
-                if let Hash(ref h) = *data {
-                    hashes.insert(0, h);
-                    hash = h;
+            if let Some(data) = hash.lookup(section) {
+                if context::is_hash(data) {
+                    hashes.insert(0, data);
+                    hash = data;
                 }
             }
         }
@@ -112,15 +320,11 @@ impl Template {
         // we end up with the previous vector plus: [{}, { "value", "foo"}, {}]
         //
         for section in sections.iter() {
-            if let Some(data) = datastore.get(section) {
-                match *data {
-                    Hash(ref h) => {
-                        hashes.insert(0, h);
-                    }
-                    Vector(_) => {
-                        return Some(data);
-                    }
-                    _ => {}
+            if let Some(data) = self.lookup(datastore, section) {
+                if context::is_hash(data) {
+                    hashes.insert(0, data);
+                } else if data.items().is_some() {
+                    return Some(data);
                 }
             }
         }
@@ -128,136 +332,273 @@ impl Template {
         // once we've assembled the vector of hashes to look through
         // we iterate through it looking for the data
         let rv = hashes.iter()
-            .filter_map(|h| h.get(key))
+            .filter_map(|&h| h.lookup(key))
             .next();
 
         // last but not least, check the top level if we didn't find anything
         if rv.is_none() {
-            return datastore.get(key);
+            return self.lookup(datastore, key);
         }
 
         rv
     }
 
-    fn handle_unescaped_lambda_interpolation<W: Write>(&mut self,
-                                                       f: &mut FnMut(String) -> String,
-                                                       data: &HashMap<String, Data>,
-                                                       raw: String,
-                                                       writer: &mut W)
-                                                       -> Result<()> {
-        let val = (*f)(raw);
-        let tokens = compiler::create_tokens(&val[..]);
-        let nodes = parser::parse_nodes(&tokens);
+    // looks a dotted name up, the first part the way section data is
+    // looked up and the rest inside it, where numbers pick items of lists
+    fn look_up_path<'b>(&self,
+                        name: &str,
+                        sections: &[String],
+                        datastore: &'b dyn Context)
+                        -> Option<&'b dyn Context>
+        where 'l: 'b
+    {
+        if self.renderer.use_context_paths() && parser::is_context_path(name) {
+            return self.look_up_context_path(name, sections, datastore);
+        }
+
+        let mut parts = name.split('.');
+        let first = parts.next().unwrap_or("");
+        let data = self.look_up_section_data(first, sections, datastore);
 
-        self.render(writer, data, &nodes)
+        parts.fold(data, |data, part| data.and_then(|data| look_up_part(data, part)))
     }
 
-    fn handle_escaped_lambda_interpolation<W: Write>(&mut self,
-                                                     f: &mut FnMut(String) -> String,
-                                                     data: &HashMap<String, Data>,
-                                                     raw: String,
-                                                     writer: &mut W)
-                                                     -> Result<()> {
-        let val = (*f)(raw);
-        let value = self.escape_html(&val[..]);
-        let tokens = compiler::create_tokens(&value[..]);
-        let nodes = parser::parse_nodes(&tokens);
+    // the hashes of the sections we're in, outermost first
+    fn section_contexts<'b>(&self,
+                            sections: &[String],
+                            datastore: &'b dyn Context)
+                            -> Vec<&'b dyn Context>
+        where 'l: 'b
+    {
+        (0..sections.len())
+            .filter_map(|i| self.look_up_section_data(&sections[i], &sections[..i], datastore))
+            .filter(|&data| context::is_hash(data))
+            .collect()
+    }
+
+    // the data with the hashes of the sections we're in around it, for
+    // rendering data that leaves the sections behind, such as the items of
+    // a list.  names the data doesn't have are looked up in the innermost
+    // section first.
+    fn section_scope<'b>(&self,
+                         sections: &[String],
+                         datastore: &'b dyn Context)
+                         -> Box<dyn Context + 'b>
+        where 'l: 'b
+    {
+        let root: Box<dyn Context + 'b> = Box::new(datastore);
+        self.section_contexts(sections, datastore)
+            .into_iter()
+            .fold(root, |parent, data| Box::new(SectionScope { data, parent }))
+    }
+
+    // looks up a name starting with `../` or `@root.`, see
+    // `Renderer::enable_context_paths`.
+    //
+    // the contexts, innermost first, are the hashes of the sections we're
+    // in, then the data and each of the contexts around it.  each `../`
+    // skips one of them, and `@root.` skips all but the last.  the rest of
+    // the name is looked up in the contexts that are left, in order.
+    fn look_up_context_path<'b>(&self,
+                                name: &str,
+                                sections: &[String],
+                                datastore: &'b dyn Context)
+                                -> Option<&'b dyn Context>
+        where 'l: 'b
+    {
+        let mut contexts = self.section_contexts(sections, datastore);
+        contexts.reverse();
+        let mut data = Some(datastore);
+        while let Some(context) = data {
+            contexts.push(context);
+            data = context.parent_context();
+        }
 
-        self.render(writer, data, &nodes)
+        let mut path = name;
+        let mut skip = 0;
+        if path.starts_with("@root.") {
+            path = &path["@root.".len()..];
+            skip = contexts.len() - 1;
+        } else {
+            while path.starts_with("../") {
+                path = &path["../".len()..];
+                skip += 1;
+            }
+        }
+        let contexts = contexts.get(skip..).unwrap_or(&[]);
+
+        let mut parts = path.split('.');
+        let first = parts.next().unwrap_or("");
+        let data = contexts.iter()
+            .filter_map(|&context| context.lookup(first))
+            .next()
+            .or_else(|| contexts.last().and_then(|&root| self.lookup(root, first)));
+
+        parts.fold(data, |data, part| data.and_then(|data| look_up_part(data, part)))
+    }
+
+    // a section named `name.@entries` renders once for each entry of the
+    // hash at `name`, when the renderer allows it
+    fn hash_entries<'b>(&self,
+                        key: &str,
+                        sections: &[String],
+                        datastore: &'b dyn Context)
+                        -> Option<Box<dyn Context + 'b>>
+        where 'l: 'b
+    {
+        if !self.renderer.use_hash_iteration() || !key.ends_with(ENTRIES_SUFFIX) {
+            return None;
+        }
+
+        let name = &key[..key.len() - ENTRIES_SUFFIX.len()];
+        let hash = self.look_up_path(name, sections, datastore);
+        let entries = hash.and_then(|hash| hash.entries()).unwrap_or_default();
+        Some(Box::new(entries.into_iter()
+            .map(|(key, value)| HashEntry { key, value })
+            .collect::<Vec<_>>()))
+    }
+
+    // key:       the tag's name followed by its filters, `name | filter:arg`
+    //
+    // the value is piped through each filter in turn, and what the last
+    // one returns is written out like any other value
+    fn handle_filtered_node<W: Write>(&mut self,
+                                      node: &Node,
+                                      key: &str,
+                                      datastore: &dyn Context,
+                                      sections: &[String],
+                                      writer: &mut W)
+                                      -> Result<()> {
+        let mut parts = split_filters(key).into_iter();
+        let name = parts.next().unwrap_or("").trim();
+        let data = self.look_up_path(name, sections, datastore);
+        let result = self.call_data_lambda(data, datastore, sections);
+        let mut value = result.as_deref().or(data);
+        let mut filtered;
+
+        for part in parts {
+            let part = part.trim();
+            let (filter, arg) = match part.find(':') {
+                Some(pos) => (part[..pos].trim(), Some(filter_argument(&part[pos + 1..]))),
+                None => (part, None),
+            };
+            let f = match self.renderer.find_filter(filter) {
+                Some(f) => f,
+                None => return Err(ErrorKind::UnknownFilter(filter.into()).into()),
+            };
+            filtered = f(value, arg);
+            value = Some(&filtered);
+        }
+
+        match value {
+            Some(value) => {
+                self.handle_unescaped_or_value_node(node,
+                                                    value,
+                                                    key.to_string(),
+                                                    datastore,
+                                                    sections,
+                                                    writer)
+            }
+            None => Ok(()),
+        }
+    }
+
+    // an interpolation lambda is called without any text, as the spec's
+    // lambdas take no arguments.  what it returns is rendered with the
+    // default delimiters, then escaped for a {{ }} tag.
+    fn handle_lambda_interpolation<W: Write>(&mut self,
+                                             node: &Node,
+                                             f: &dyn Context,
+                                             datastore: &dyn Context,
+                                             sections: &[String],
+                                             writer: &mut W)
+                                             -> Result<()> {
+        let val = f.call_lambda("".to_string()).unwrap_or_default();
+        let tokens = compiler::create_tokens(&val[..]);
+        let nodes = parser::parse_nodes(&tokens);
+        let mut out = Vec::new();
+        try!(self.render_section_children(&nodes, datastore, &mut sections.to_vec(), &mut out));
+
+        let rendered = String::from_utf8_lossy(&out);
+        let tmp = match *node {
+            Unescaped(_, _) => rendered.into_owned(),
+            Value(_, _) => self.escape_html(&rendered),
+            _ => return Err(ErrorKind::UnexpectedNodeType(format!("{:?}", node)).into()),
+        };
+        self.write_to_stream(writer, &tmp, "render: lambda interpolation")
     }
 
     // data:      the data value for the tag/node we're handling
     // key:       the name of the tag we're handling, i.e. the key into the data hash
     // datastore: all the data for the template
+    // sections:  the sections we're in, for lambdas to look data up
     // writer:    the output stream to write rendered template to
     //
-    // the Data enum, which is how we hold different types of data in one hash,
-    // can be, well, several different types.  this method matches them all and
-    // handles the data appropriately.
+    // the data can be, well, several different kinds of value.  this method
+    // checks for each of them and handles the data appropriately.
     //
-    // TODO: really don't need to be handling Bool, Vector or Hash
+    // TODO: really don't need to be handling lists or hashes
     fn handle_unescaped_or_value_node<W: Write>(&mut self,
                                                 node: &Node,
-                                                data: &Data,
+                                                data: &dyn Context,
                                                 key: String,
-                                                datastore: &HashMap<String, Data>,
+                                                datastore: &dyn Context,
+                                                sections: &[String],
                                                 writer: &mut W)
                                                 -> Result<()> {
-        let mut rv = Ok(());
-        let mut tmp: String = String::new();
-        match *data {
-            // simple value-for-tag exchange, write out the string
-            Data::String(ref val) => {
-                match *node {
-                    Unescaped(_, _) => tmp = tmp + val,
-                    Value(_, _) => tmp = self.escape_html(&val[..]),
-                    _ => return Err(ErrorKind::UnexpectedNodeType(format!("{:?}", node)).into()),
-                }
-                rv = self.write_to_stream(writer, &tmp, "render: unescaped node string fail");
-            }
-            // TODO: this one doesn't quite make sense.  i don't think we need it.
-            Bool(ref val) => {
-                if *val {
-                    tmp.push_str("true")
-                } else {
-                    tmp.push_str("false")
-                }
-                rv = self.write_to_stream(writer, &tmp, "render: unescaped node bool");
-            }
-            // if the data is an integer, convert it to a string and write that
-            Integer(ref val) => {
-                tmp = tmp + &val.to_string();
-                rv = self.write_to_stream(writer, &tmp, "render: unescaped node int");
-            }
-            // if the data is a float, convert it to a string and write that
-            Float(ref val) => {
-                tmp = tmp + &val.to_string();
-                rv = self.write_to_stream(writer, &tmp, "render: unescaped node float");
-            }
-            // TODO: this one doesn't quite make sense.  i don't think we need it.
-            Vector(ref list) => {
-                for item in list.iter() {
-                    try!(self.handle_unescaped_or_value_node(node,
-                                                             item,
-                                                             key.to_string(),
-                                                             datastore,
-                                                             writer));
-                }
-            }
-            // TODO: this one doesn't quite make sense.  i don't think we need it.
-            Hash(ref hash) => {
-                if hash.contains_key(&key) {
-                    let tmp = &hash[&key];
-                    try!(self.handle_unescaped_or_value_node(node,
-                                                             tmp,
-                                                             key.to_string(),
-                                                             datastore,
-                                                             writer));
-                }
+        // if we have a lambda for the data, the return value of the
+        // lambda is what we substitute for the tag
+        if data.is_lambda() {
+            let mut context = LambdaContext {
+                template: self,
+                datastore,
+                sections,
+            };
+            if let Some(val) = data.call_context_lambda("", &mut context) {
+                let tmp = match *node {
+                    Unescaped(_, _) => val,
+                    _ => self.escape_html(&val),
+                };
+                return self.write_to_stream(writer, &tmp, "render: context lambda");
             }
-            // if we have a lambda for the data, the return value of the
-            // lambda is what we substitute for the tag
-            Lambda(ref f) => {
-                let raw = "".to_string();
-                match *node {
-                    Unescaped(_, _) => {
-                        rv = self.handle_unescaped_lambda_interpolation(&mut *f.borrow_mut(),
-                                                                        datastore,
-                                                                        raw,
-                                                                        writer)
-                    }
-                    Value(_, _) => {
-                        rv = self.handle_escaped_lambda_interpolation(&mut *f.borrow_mut(),
-                                                                      datastore,
-                                                                      raw,
-                                                                      writer)
-                    }
-                    _ => return Err(ErrorKind::UnexpectedNodeType(format!("{:?}", node)).into()),
-                }
+
+            return self.handle_lambda_interpolation(node, data, datastore, sections, writer);
+        }
+
+        // simple value-for-tag exchange, write out the text
+        if let Some(val) = data.interpolate() {
+            let tmp = match *node {
+                Unescaped(_, _) => val.into_owned(),
+                Value(_, _) => self.escape_html(&val),
+                _ => return Err(ErrorKind::UnexpectedNodeType(format!("{:?}", node)).into()),
+            };
+            return self.write_to_stream(writer, &tmp, "render: unescaped node value");
+        }
+
+        // TODO: this one doesn't quite make sense.  i don't think we need it.
+        if let Some(list) = data.items() {
+            for item in list {
+                try!(self.handle_unescaped_or_value_node(node,
+                                                         &*item,
+                                                         key.to_string(),
+                                                         datastore,
+                                                         sections,
+                                                         writer));
             }
+            return Ok(());
         }
 
-        rv
+        // TODO: this one doesn't quite make sense.  i don't think we need it.
+        if let Some(tmp) = data.lookup(&key) {
+            try!(self.handle_unescaped_or_value_node(node,
+                                                     tmp,
+                                                     key.to_string(),
+                                                     datastore,
+                                                     sections,
+                                                     writer));
+        }
+
+        Ok(())
     }
 
     // nodes:     children of the inverted section tag
@@ -269,7 +610,7 @@ impl Template {
     //
     fn handle_inverted_node<W: Write>(&mut self,
                                       nodes: &[Node],
-                                      datastore: &HashMap<String, Data>,
+                                      datastore: &dyn Context,
                                       writer: &mut W)
                                       -> Result<()> {
         let mut rv = Ok(());
@@ -285,24 +626,36 @@ impl Template {
                 Part(filename, _) => {
                     rv = self.handle_partial_file_node(filename, datastore, writer);
                 }
-                Section(key, ref children, ref inverted, _, _) => {
+                Section(key, ref children, ref inverted, _, _, delimiters) => {
                     let tmp = key.to_string();
-                    let truthy = if datastore.contains_key(&tmp) {
-                        self.is_section_data_true(&datastore[&tmp])
-                    } else {
-                        false
+                    let data = self.lookup(datastore, &tmp);
+                    let result = self.call_data_lambda(data, datastore, &[]);
+                    let entries = self.hash_entries(&tmp, &[], datastore);
+                    let data = entries.as_deref().or(result.as_deref()).or(data);
+                    let truthy = match data {
+                        Some(val) => self.is_section_data_true(val),
+                        None => false,
                     };
                     match (truthy, *inverted) {
                         (true, true) | (false, false) => {}
                         (true, false) => {
-                            let val = &datastore[&tmp];
-                            let mut sections = vec![tmp.clone()];
-                            rv = self.handle_section_node(children,
-                                                          &tmp,
-                                                          val,
-                                                          datastore,
-                                                          &mut sections,
-                                                          writer);
+                            let val = data.unwrap();
+                            if result.is_some() {
+                                rv = self.handle_data_lambda_section(children,
+                                                                     delimiters,
+                                                                     val,
+                                                                     datastore,
+                                                                     &[],
+                                                                     writer);
+                            } else {
+                                let mut sections = vec![tmp.clone()];
+                                rv = self.handle_section_node(children,
+                                                              delimiters,
+                                                              val,
+                                                              datastore,
+                                                              &mut sections,
+                                                              writer);
+                            }
                         }
                         (false, true) => {
                             rv = self.handle_inverted_node(children, datastore, writer);
@@ -311,81 +664,136 @@ impl Template {
                 }
                 _ => {}
             }
+            if rv.is_err() {
+                break;
+            }
         }
 
         rv
     }
 
-    // nodes:     the section's children
-    // data:      data from section key from HashBuilder store
-    // datastore: HashBuilder data
-    // writer:    io stream
+    // nodes:      the section's children
+    // delimiters: the delimiters the section was opened with
+    // data:       data from section key from HashBuilder store
+    // datastore:  HashBuilder data
+    // writer:     io stream
     fn handle_section_node<W: Write>(&mut self,
                                      nodes: &[Node],
-                                     _: &str,
-                                     data: &Data,
-                                     datastore: &HashMap<String, Data>,
+                                     delimiters: (&str, &str),
+                                     data: &dyn Context,
+                                     datastore: &dyn Context,
                                      sections: &mut Vec<String>,
                                      writer: &mut W)
                                      -> Result<()> {
         let mut rv = Ok(());
         // there's a special case if the section tag data was a lambda
-        // if so, the lambda is used to generate the values for the tag inside the section
-        match *data {
-            Lambda(ref f) => {
-                let raw = self.get_section_text(nodes);
-                return self.handle_unescaped_lambda_interpolation(&mut *f.borrow_mut(),
-                                                                  datastore,
-                                                                  raw,
-                                                                  writer);
+        // if so, the lambda is given the raw text of the section, and what
+        // it returns is rendered in its place with the section's delimiters
+        if data.is_lambda() {
+            let raw = self.get_section_text(nodes);
+            // the text is rendered in the sections around the lambda's own,
+            // so that `.` isn't the lambda
+            let mut sections = sections[..sections.len().saturating_sub(1)].to_vec();
+            let mut context = LambdaContext {
+                template: self,
+                datastore,
+                sections: &sections,
+            };
+            if let Some(val) = data.call_context_lambda(&raw, &mut context) {
+                return self.write_to_stream(writer, &val, "render: section context lambda");
             }
-            Vector(ref v) => {
-                for d in v.iter() {
-                    for node in nodes.iter() {
-                        match *d {
-                            Hash(ref h) => {
-                                rv = self.handle_node(node, h, writer);
-                            }
-                            Data::String(ref val) => {
-                                return Err(ErrorKind::UnexpectedDataType(val.to_owned()).into())
-                            }
-                            Bool(ref val) => {
-                                return Err(ErrorKind::UnexpectedDataType(format!("{}", val)).into())
-                            }
-                            Integer(ref val) => {
-                                return Err(ErrorKind::UnexpectedDataType(format!("{}", val)).into())
-                            }
-                            Float(ref val) => {
-                                return Err(ErrorKind::UnexpectedDataType(format!("{}", val)).into())
-                            }
-                            Vector(ref val) => {
-                                return Err(ErrorKind::UnexpectedDataType(format!("{:?}", val)).into())
-                            }
-                            Lambda(_) => {
-                                return Err(ErrorKind::UnexpectedDataType("lambda".into()).into())
-                            }
-                        }
-                    }
+
+            let val = data.call_lambda(raw).unwrap_or_default();
+            let (open, close) = delimiters;
+            let tokens = compiler::create_tokens_with(&val, open, close);
+            let nodes = parser::parse_nodes_with(&tokens, delimiters);
+            return self.render_section_children(&nodes, datastore, &mut sections, writer);
+        }
+
+        // for a list, the children are rendered once for each item, with the
+        // item as the data.  names the item doesn't have are looked up in the
+        // data around the list, and `.` is the item itself.
+        if let Some(list) = data.items() {
+            // the loop variables need the length up front, so the items
+            // are read before the first is rendered
+            let (list, length): (Items, usize) = if self.renderer.use_loop_variables() {
+                let items: Vec<_> = list.collect();
+                let length = items.len();
+                (Box::new(items.into_iter()), length)
+            } else {
+                (list, 0)
+            };
+
+            // the data around the items includes the hashes of the sections
+            // the list is in
+            let scope = self.section_scope(sections, datastore);
+            let datastore = &*scope;
+
+            for (index, d) in list.enumerate() {
+                let result = self.call_data_lambda(Some(&*d), datastore, sections);
+                let d = result.as_deref().unwrap_or(&*d);
+                let looped = LoopItem {
+                    data: d,
+                    index,
+                    index1: index + 1,
+                    first: index == 0,
+                    last: index + 1 == length,
+                    length,
+                };
+                let data: &dyn Context = if self.renderer.use_loop_variables() {
+                    &looped
+                } else {
+                    d
+                };
+                if d.is_lambda() {
+                    return Err(ErrorKind::UnexpectedDataType("lambda".into()).into());
+                }
+                let item = Scope {
+                    data,
+                    parent: datastore,
+                };
+                rv = nodes.iter().try_for_each(|node| self.handle_node(node, &item, writer));
+                if rv.is_err() {
+                    break;
                 }
-                return rv;
             }
-            _ => {}
+            return rv;
         }
 
+        self.render_section_children(nodes, datastore, sections, writer)
+    }
+
+    // nodes:     the section's children
+    // datastore: HashBuilder data
+    // sections:  the sections we're in, for looking data up
+    // writer:    io stream
+    fn render_section_children<W: Write>(&mut self,
+                                         nodes: &[Node],
+                                         datastore: &dyn Context,
+                                         sections: &mut Vec<String>,
+                                         writer: &mut W)
+                                         -> Result<()> {
+        let mut rv = Ok(());
+
         // in a section tag, there are child tags to fill out,
         // we need to iterate through each one
         for node in nodes.iter() {
             match *node {
                 // unescaped is simple, just look up the data in the
                 // special way sections need to and handle the node
+                Unescaped(key, _) | Value(key, _) if self.is_filtered(key) => {
+                    rv = self.handle_filtered_node(node, key, datastore, sections, writer);
+                }
                 Unescaped(key, _) | Value(key, _) => {
                     let tmpkey = key.to_string();
                     let tmpdata = self.look_up_section_data(&tmpkey, sections, datastore);
-                    if tmpdata.is_some() {
+                    let result = self.call_data_lambda(tmpdata, datastore, sections);
+                    if let Some(tmpdata) = result.as_deref().or(tmpdata) {
                         rv = self.handle_unescaped_or_value_node(node,
-                                                                 tmpdata.unwrap(),
+                                                                 tmpdata,
                                                                  key.to_string(),
                                                                  datastore,
+                                                                 sections,
                                                                  writer);
                     }
                 }
@@ -397,7 +805,7 @@ impl Template {
                                              "render: section node static");
                 }
                 // sections are special and may be inverted
-                Section(key, ref children, ref inverted, _, _) => {
+                Section(key, ref children, ref inverted, _, _, delimiters) => {
                     if !*inverted {
                         // A normal, not inverted tag is more complicated and may recurse
                         // we need to save what sections we have been in, so the data
@@ -405,17 +813,50 @@ impl Template {
                         let tmpkey = key.to_string();
                         sections.push(tmpkey.clone());
                         let tmpdata = self.look_up_section_data(&tmpkey, sections, datastore);
-                        if tmpdata.is_some() {
-                            rv = self.handle_section_node(children,
-                                                          &tmpkey,
-                                                          tmpdata.unwrap(),
-                                                          datastore,
-                                                          sections,
-                                                          writer);
+                        let result = self.call_data_lambda(tmpdata, datastore, sections);
+                        let entries = self.hash_entries(&tmpkey, sections, datastore);
+                        if let Some(ref entries) = entries {
+                            if self.is_section_data_true(&**entries) {
+                                rv = self.handle_section_node(children,
+                                                              delimiters,
+                                                              &**entries,
+                                                              datastore,
+                                                              sections,
+                                                              writer);
+                            }
+                        } else if let Some(ref result) = result {
+                            rv = self.handle_data_lambda_section(children,
+                                                                 delimiters,
+                                                                 &**result,
+                                                                 datastore,
+                                                                 sections,
+                                                                 writer);
+                        } else if let Some(tmpdata) = tmpdata {
+                            if self.is_section_data_true(tmpdata) {
+                                rv = self.handle_section_node(children,
+                                                              delimiters,
+                                                              tmpdata,
+                                                              datastore,
+                                                              sections,
+                                                              writer);
+                            }
                         }
+                        // the sections after this one aren't inside it
+                        sections.pop();
                     } else {
-                        // inverted only has internal static text, so is easy to handle
-                        rv = self.handle_inverted_node(children, datastore, writer);
+                        // inverted only has internal static text, so is easy to
+                        // handle once we know the data is false
+                        let tmpkey = key.to_string();
+                        let tmpdata = self.look_up_section_data(&tmpkey, sections, datastore);
+                        let result = self.call_data_lambda(tmpdata, datastore, sections);
+                        let entries = self.hash_entries(&tmpkey, sections, datastore);
+                        let truthy = match entries.as_deref().or(result.as_deref()).or(tmpdata) {
+                            Some(val) => self.is_section_data_true(val),
+                            None => false,
+                        };
+                        if !truthy {
+                            rv = self.handle_inverted_node(children, datastore, writer);
+                        }
                     }
                 }
                 // if it's a partial, we have a file to read in and render
@@ -423,22 +864,46 @@ impl Template {
                     rv = self.handle_partial_file_node(path, datastore, writer);
                 }
             }
+            if rv.is_err() {
+                break;
+            }
         }
 
         rv
     }
 
-    // section data is considered false in a few cases:
-    // there is no data for the key in the data hashmap
-    // the data is a bool with a value of false
-    // the data is an empty vector
-    fn is_section_data_true(&self, data: &Data) -> bool {
-        match *data {
-            // if the data is a bool, rv is just the bool value
-            Bool(value) => value,
-            Vector(ref vec) => !vec.is_empty(),
-            _ => true,
+    // renders a section over the data a data lambda returned, where names
+    // are looked up in that data before the sections around it and the data
+    fn handle_data_lambda_section<W: Write>(&mut self,
+                                            nodes: &[Node],
+                                            delimiters: (&str, &str),
+                                            data: &dyn Context,
+                                            datastore: &dyn Context,
+                                            sections: &[String],
+                                            writer: &mut W)
+                                            -> Result<()> {
+        if !self.is_section_data_true(data) {
+            return Ok(());
         }
+
+        let parent = self.section_scope(sections, datastore);
+        let scope = Scope {
+            data,
+            parent: &*parent,
+        };
+        self.handle_section_node(nodes, delimiters, data, &scope, &mut Vec::new(), writer)
+    }
+
+    // whether a tag's name pipes its value through filters, which it only
+    // can when the renderer allows them
+    fn is_filtered(&self, key: &str) -> bool {
+        self.renderer.use_filters() && key.contains('|')
+    }
+
+    // section data is considered false when there is no data for the key,
+    // otherwise the renderer's truthiness rule decides, see `Truthiness`
+    fn is_section_data_true(&self, data: &dyn Context) -> bool {
+        self.renderer.is_truthy(data)
     }
 
     // children: a vector of nodes representing the template text
@@ -456,7 +921,7 @@ impl Template {
                 Static(text) | Part(_, text) => text.into(),
                 Value(_, ref text) |
                 Unescaped(_, ref text) => String::from(&text[..]),
-                Section(_, ref children, _, ref open, ref close) => {
+                Section(_, ref children, _, ref open, ref close, _) => {
                     let rv = self.get_section_text(children);
                     format!("{}{}{}", &open[..], &rv[..], &close[..])
                 }
@@ -477,7 +942,7 @@ impl Template {
     //
     fn handle_partial_file_node<W: Write>(&mut self,
                                           filename: &str,
-                                          datastore: &HashMap<String, Data>,
+                                          datastore: &dyn Context,
                                           writer: &mut W)
                                           -> Result<()> {
         let path = Path::new(&self.partials_path.clone()).join(filename);
@@ -504,7 +969,7 @@ impl Template {
 
     fn handle_node<W: Write>(&mut self,
                              node: &Node,
-                             datastore: &HashMap<String, Data>,
+                             datastore: &dyn Context,
                              writer: &mut W)
                              -> Result<()> {
         let mut rv = Ok(());
@@ -513,14 +978,20 @@ impl Template {
             // value nodes contain tags who's data gets HTML escaped
             // when it gets written out
             Unescaped(key, _) |
+            Value(key, _) if self.is_filtered(key) => {
+                rv = self.handle_filtered_node(node, key, datastore, &[], writer);
+            }
+            Unescaped(key, _) |
             Value(key, _) => {
                 let tmp = key.to_string();
-                if datastore.contains_key(&tmp) {
-                    let val = &datastore[&tmp];
+                let data = self.lookup(datastore, &tmp);
+                let result = self.call_data_lambda(data, datastore, &[]);
+                if let Some(val) = result.as_deref().or(data) {
                     rv = self.handle_unescaped_or_value_node(node,
                                                              val,
                                                              "".to_string(),
                                                              datastore,
+                                                             &[],
                                                              writer);
                 }
             }
@@ -536,24 +1007,36 @@ impl Template {
             //
             // normal section tags enclose a bit of html that will get repeated
             // for each element found in it's data
-            Section(key, ref children, ref inverted, _, _) => {
+            Section(key, ref children, ref inverted, _, _, delimiters) => {
                 let tmp = key.to_string();
-                let truthy = if datastore.contains_key(&tmp) {
-                    self.is_section_data_true(&datastore[&tmp])
-                } else {
-                    false
+                let data = self.lookup(datastore, &tmp);
+                let result = self.call_data_lambda(data, datastore, &[]);
+                let entries = self.hash_entries(&tmp, &[], datastore);
+                let data = entries.as_deref().or(result.as_deref()).or(data);
+                let truthy = match data {
+                    Some(val) => self.is_section_data_true(val),
+                    None => false,
                 };
                 match (truthy, *inverted) {
                     (true, true) | (false, false) => {}
                     (true, false) => {
-                        let val = &datastore[&tmp];
-                        let mut sections = vec![tmp.clone()];
-                        rv = self.handle_section_node(children,
-                                                      &tmp,
-                                                      val,
-                                                      datastore,
-                                                      &mut sections,
-                                                      writer);
+                        let val = data.unwrap();
+                        if result.is_some() {
+                            rv = self.handle_data_lambda_section(children,
+                                                                 delimiters,
+                                                                 val,
+                                                                 datastore,
+                                                                 &[],
+                                                                 writer);
+                        } else {
+                            let mut sections = vec![tmp.clone()];
+                            rv = self.handle_section_node(children,
+                                                          delimiters,
+                                                          val,
+                                                          datastore,
+                                                          &mut sections,
+                                                          writer);
+                        }
                     }
                     (false, true) => {
                         rv = self.handle_inverted_node(children, datastore, writer);
@@ -570,11 +1053,11 @@ impl Template {
         rv
     }
     // writer: an io::stream to write the rendered template out to
-    // data:   the internal HashBuilder data store
+    // data:   the data to render, usually the internal HashBuilder data store
     // parser: the parser object that has the parsed nodes, see src/parse.js
     pub fn render<W: Write>(&mut self,
                             writer: &mut W,
-                            data: &HashMap<String, Data>,
+                            data: &dyn Context,
                             nodes: &[Node])
                             -> Result<()> {
         // nodes are what the template file is parsed into
@@ -599,8 +1082,56 @@ impl Template {
 
         self.render(writer, &datastore.data, nodes)
     }
+
+    // renders the last layer, falling back on the ones before it
+    pub fn render_layers<W: Write>(&mut self,
+                                   writer: &mut W,
+                                   layers: &'l Layers,
+                                   nodes: &[Node])
+                                   -> Result<()> {
+        self.partials_path.truncate(0);
+        self.partials_path.push_str(layers.partials_path);
+
+        match layers.layers.split_last() {
+            Some((data, rest)) => {
+                self.layers = rest;
+                self.render(writer, &**data, nodes)
+            }
+            None => self.render(writer, &HashMap::<String, Data>::new(), nodes),
+        }
+    }
 }
 
+// splits a tag's name from the filters it is piped through, leaving the
+// `|` inside quoted arguments alone
+fn split_filters(key: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    for (i, c) in key.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '|' if !quoted => {
+                parts.push(&key[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&key[start..]);
+    parts
+}
+
+// the text of a filter's argument, which may be quoted to keep the
+// whitespace around it
+fn filter_argument(arg: &str) -> &str {
+    let arg = arg.trim();
+    if arg.len() >= 2 && arg.starts_with('"') && arg.ends_with('"') {
+        &arg[1..arg.len() - 1]
+    } else {
+        arg
+    }
+}
 
 #[cfg(test)]
 mod template_tests {
@@ -614,8 +1145,8 @@ mod template_tests {
     use parser::Node::{Value, Static, Unescaped, Section, Part};
     use compiler;
     use template::Template;
+    use renderer::Renderer;
     use build::{HashBuilder, VecBuilder};
-    use Data;
 
     #[test]
     fn test_look_up_section_data() {
@@ -639,13 +1170,14 @@ mod template_tests {
                             "e".to_string()];
         let data = hb.data;
 
-        let answer = Template::new().look_up_section_data(&key, &sections, &data);
+        let renderer = Renderer::new();
+        let answer = Template::new(&renderer).look_up_section_data(&key, &sections, &data);
 
         assert!(answer.is_some());
         match answer {
             Some(d) => {
-                match *d {
-                    Data::String(ref s) => assert_eq!("Phil".to_string(), *s),
+                match d.interpolate() {
+                    Some(s) => assert_eq!("Phil", s),
                     _ => {
                         assert!(false);
                     }
@@ -674,13 +1206,14 @@ mod template_tests {
                             "e".to_string()];
         let data = hb.data;
 
-        let answer = Template::new().look_up_section_data(&key, &sections, &data);
+        let renderer = Renderer::new();
+        let answer = Template::new(&renderer).look_up_section_data(&key, &sections, &data);
 
         assert!(answer.is_some());
         match answer {
             Some(d) => {
-                match *d {
-                    Data::String(ref s) => assert_eq!("Phil".to_string(), *s),
+                match d.interpolate() {
+                    Some(s) => assert_eq!("Phil", s),
                     _ => {
                         assert!(false);
                     }
@@ -703,7 +1236,7 @@ mod template_tests {
         let nodes: Vec<Node> = vec![Value("value", "{{ value }}".to_string())];
         let data = HashBuilder::new().insert("value", s1);
 
-        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        let rv = Template::new(&Renderer::new()).render_data(&mut w, &data, &nodes);
         match rv {
             _ => {}
         }
@@ -712,7 +1245,7 @@ mod template_tests {
 
         w = Cursor::new(Vec::new());
         let newdata = HashBuilder::new().insert("value", s2);
-        let rv = Template::new().render_data(&mut w, &newdata, &nodes);
+        let rv = Template::new(&Renderer::new()).render_data(&mut w, &newdata, &nodes);
         match rv {
             _ => {}
         }
@@ -733,7 +1266,7 @@ mod template_tests {
                                                  .push(HashBuilder::new().insert("name", "hub"))
                                                  .push(HashBuilder::new().insert("name", "rip")));
 
-        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        let rv = Template::new(&Renderer::new()).render_data(&mut w, &data, &nodes);
         match rv {
             _ => {}
         }
@@ -749,7 +1282,7 @@ mod template_tests {
         let nodes: Vec<Node> = vec![Unescaped("value", "{{ value }}".to_string())];
         let data = HashBuilder::new().insert("value", s);
 
-        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        let rv = Template::new(&Renderer::new()).render_data(&mut w, &data, &nodes);
         match rv {
             _ => {}
         }
@@ -764,7 +1297,7 @@ mod template_tests {
         let nodes: Vec<Node> =
             vec![Static("<h1>"), Value("value1", "{{ value1 }}".to_string()), Static("</h1>")];
 
-        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        let rv = Template::new(&Renderer::new()).render_data(&mut w, &data, &nodes);
         match rv {
             _ => {}
         }
@@ -780,7 +1313,7 @@ mod template_tests {
             vec![Static("<h1>"), Unescaped("value1", "{{& value1 }}".to_string()), Static("</h1>")];
         let data = HashBuilder::new().insert("value1", false);
 
-        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        let rv = Template::new(&Renderer::new()).render_data(&mut w, &data, &nodes);
         match rv {
             _ => {}
         }
@@ -796,7 +1329,7 @@ mod template_tests {
             vec![Static("<h1>"), Unescaped("value1", "{{& value1 }}".to_string()), Static("</h1>")];
         let data = HashBuilder::new().insert("value1", true);
 
-        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        let rv = Template::new(&Renderer::new()).render_data(&mut w, &data, &nodes);
         match rv {
             _ => {}
         }
@@ -812,11 +1345,12 @@ mod template_tests {
                                             vec![Value("value", "{{ value }}".to_string())],
                                             false,
                                             "{{# value1 }}".to_string(),
-                                            "{{/ value1 }}".to_string())];
+                                            "{{/ value1 }}".to_string(),
+                                            ("{{", "}}"))];
         let data = HashBuilder::new().insert("value1",
                                              HashBuilder::new().insert("value", "<Section Value>"));
 
-        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        let rv = Template::new(&Renderer::new()).render_data(&mut w, &data, &nodes);
         match rv {
             _ => {}
         }
@@ -832,7 +1366,8 @@ mod template_tests {
                                             vec![Value("name", "{{ name }}".to_string())],
                                             false,
                                             "{{# names }}".to_string(),
-                                            "{{/ names }}".to_string())];
+                                            "{{/ names }}".to_string(),
+                                            ("{{", "}}"))];
         let data = HashBuilder::new().insert("names",
                                              HashBuilder::new().insert("name",
                                                                        VecBuilder::new()
@@ -840,7 +1375,7 @@ mod template_tests {
                                                                            .push("robert")
                                                                            .push("joe")));
 
-        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        let rv = Template::new(&Renderer::new()).render_data(&mut w, &data, &nodes);
         match rv {
             _ => {}
         }
@@ -852,7 +1387,7 @@ mod template_tests {
     // #[test]
     // fn test_excessively_nested_data() {
     //     let mut w = Cursor::new(Vec::new());
-    //     let nodes: Vec<Node> = vec![Section("hr", vec![Section("people", vec![Value("name", "{{ name }}".to_string())], false, "{{# people }}".to_string(), "{{/ people }}".to_string())], false, "{{# hr }}".to_string(), "{{/ hr }}".to_string())];
+    //     let nodes: Vec<Node> = vec![Section("hr", vec![Section("people", vec![Value("name", "{{ name }}".to_string())], false, "{{# people }}".to_string(), "{{/ people }}".to_string(), ("{{", "}}"))], false, "{{# hr }}".to_string(), "{{/ hr }}".to_string(), ("{{", "}}"))];
     //     let data = HashBuilder::new()
     //         .insert_hash("hr", |builder| {
     //             builder.insert_hash("people", |builder| {
@@ -866,7 +1401,7 @@ mod template_tests {
     //             })
     //         });
 
-    //     let rv = Template::new().render_data(&mut w, &data, &nodes);
+    //     let rv = Template::new(&Renderer::new()).render_data(&mut w, &data, &nodes);
     //     assert_eq!("tomrobertjoe".to_string(), String::from_utf8(w.into_inner()).unwrap());
     // }
 
@@ -878,7 +1413,7 @@ mod template_tests {
         let mut f = |_| "heading".to_string();
         let data = HashBuilder::new().insert_lambda("func1", &mut f);
 
-        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        let rv = Template::new(&Renderer::new()).render_data(&mut w, &data, &nodes);
         match rv {
             _ => {}
         }
@@ -895,7 +1430,7 @@ mod template_tests {
         let mut f = |_| "heading".to_string();
         let data = HashBuilder::new().insert_lambda("func1", &mut f);
 
-        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        let rv = Template::new(&Renderer::new()).render_data(&mut w, &data, &nodes);
         match rv {
             _ => {}
         }
@@ -934,7 +1469,7 @@ mod template_tests {
         let nodes: Vec<Node> = vec![Value("value1", "{{ value1 }}".to_string())];
         let data = HashBuilder::new().insert("value1", false);
 
-        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        let rv = Template::new(&Renderer::new()).render_data(&mut w, &data, &nodes);
         match rv {
             _ => {}
         }
@@ -949,7 +1484,7 @@ mod template_tests {
         let nodes: Vec<Node> = vec![Value("value1", "{{ value1 }}".to_string())];
         let data = HashBuilder::new().insert("value1", true);
 
-        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        let rv = Template::new(&Renderer::new()).render_data(&mut w, &data, &nodes);
         match rv {
             _ => {}
         }
@@ -971,7 +1506,7 @@ mod template_tests {
         s.push_str("A wise woman once said: It's easier to get forgiveness than \
                     permission.-Grace Hopper");
 
-        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        let rv = Template::new(&Renderer::new()).render_data(&mut w, &data, &nodes);
         match rv {
             _ => {}
         }
@@ -995,7 +1530,7 @@ mod template_tests {
         s.push_str("A wise woman once said: It's easier to get forgiveness than \
                     permission.-Grace Hopper something else extra data");
 
-        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        let rv = Template::new(&Renderer::new()).render_data(&mut w, &data, &nodes);
         match rv {
             _ => {}
         }
@@ -1027,7 +1562,7 @@ mod template_tests {
         let mut tokens = compiler::create_tokens(&contents[..]);
         let nodes = parser::parse_nodes(&mut tokens);
 
-        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        let rv = Template::new(&Renderer::new()).render_data(&mut w, &data, &nodes);
         match rv {
             _ => {}
         }
@@ -1061,7 +1596,7 @@ mod template_tests {
             .insert_lambda("lambda", &mut f)
             .insert("planet", "world");
 
-        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        let rv = Template::new(&Renderer::new()).render_data(&mut w, &data, &nodes);
         match rv {
             _ => {}
         }
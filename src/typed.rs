@@ -0,0 +1,224 @@
+use std::io::Write;
+use std::marker::PhantomData;
+
+use build::HashBuilder;
+use rustache::Render;
+use errors::*;
+
+/// A template bound to a type with `#[derive(Mustache)]` and
+/// `#[mustache(template = "path")]`, checked against the type's fields
+/// at compile time
+pub trait TypedTemplate: Sized {
+    /// The source of the bound template
+    const TEMPLATE: &'static str;
+
+    /// Render the bound template with `self` as its data
+    ///
+    /// ```rust,ignore
+    /// #[derive(Mustache)]
+    /// #[mustache(template = "templates/hero.html")]
+    /// struct Hero {
+    ///     name: String,
+    /// }
+    ///
+    /// let mut out = Cursor::new(Vec::new());
+    /// Hero { name: "Anduin".to_string() }.render(&mut out).unwrap();
+    /// ```
+    fn render<W: Write>(self, writer: &mut W) -> Result<()>
+        where Self: Into<HashBuilder<'static>>
+    {
+        let data: HashBuilder = self.into();
+        data.render(Self::TEMPLATE, writer)
+    }
+}
+
+// The shape of a derived field, recorded so that templates bound to one
+// type can be checked when they reach into the fields of another
+#[doc(hidden)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FieldShape {
+    Scalar,
+    Bool,
+    Option,
+    List,
+    Hash,
+    Unknown,
+}
+
+/// The fields of a derived struct, by the key they are inserted under.
+/// Templates that don't fit the fields of the struct they are bound to,
+/// or of the structs nested in it, don't compile:
+///
+/// ```rust,compile_fail
+/// extern crate rustache;
+/// #[macro_use]
+/// extern crate rustache_derive;
+/// # #[derive(Mustache)] struct Card { name: String }
+/// # #[derive(Mustache)] struct Stats { alive: bool }
+///
+/// #[derive(Mustache)]
+/// #[mustache(template = "test_data/typed_missing_field.html")]
+/// struct Hero {
+///     name: String,
+///     cards: Vec<Card>,
+///     stats: Stats,
+/// }
+/// # fn main() {}
+/// ```
+///
+/// ```rust,compile_fail
+/// extern crate rustache;
+/// #[macro_use]
+/// extern crate rustache_derive;
+/// # #[derive(Mustache)] struct Card { name: String }
+/// # #[derive(Mustache)] struct Stats { alive: bool }
+///
+/// #[derive(Mustache)]
+/// #[mustache(template = "test_data/typed_list_as_value.html")]
+/// struct Hero {
+///     name: String,
+///     cards: Vec<Card>,
+///     stats: Stats,
+/// }
+/// # fn main() {}
+/// ```
+///
+/// ```rust,compile_fail,E0080
+/// extern crate rustache;
+/// #[macro_use]
+/// extern crate rustache_derive;
+/// # #[derive(Mustache)] struct Card { name: String }
+/// # #[derive(Mustache)] struct Stats { alive: bool }
+///
+/// #[derive(Mustache)]
+/// #[mustache(template = "test_data/typed_nested_missing_field.html")]
+/// struct Hero {
+///     name: String,
+///     cards: Vec<Card>,
+///     stats: Stats,
+/// }
+/// # fn main() {}
+/// ```
+///
+/// ```rust,compile_fail,E0080
+/// extern crate rustache;
+/// #[macro_use]
+/// extern crate rustache_derive;
+/// # #[derive(Mustache)] struct Card { name: String }
+/// # #[derive(Mustache)] struct Stats { alive: bool }
+///
+/// #[derive(Mustache)]
+/// #[mustache(template = "test_data/typed_struct_as_value.html")]
+/// struct Hero {
+///     name: String,
+///     cards: Vec<Card>,
+///     stats: Stats,
+/// }
+/// # fn main() {}
+/// ```
+#[doc(hidden)]
+pub trait Fields {
+    const FIELDS: &'static [(&'static str, FieldShape)];
+}
+
+// The fields of a type that may not derive `Mustache`, for the const
+// assertions.  The inherent `FIELDS` only applies to types implementing
+// `Fields`, any other type falls back to the `NoFields` one.
+#[doc(hidden)]
+pub struct FieldsOf<T: ?Sized>(PhantomData<T>);
+
+impl<T: Fields + ?Sized> FieldsOf<T> {
+    #[doc(hidden)]
+    pub const FIELDS: Option<&'static [(&'static str, FieldShape)]> = Some(T::FIELDS);
+}
+
+#[doc(hidden)]
+pub trait NoFields {
+    #[doc(hidden)]
+    const FIELDS: Option<&'static [(&'static str, FieldShape)]> = None;
+}
+
+impl<T: ?Sized> NoFields for FieldsOf<T> {}
+
+impl FieldShape {
+    // Whether `fields` has a field `key` that can be used as a section,
+    // or as a value when `section` is false.  This runs in the const
+    // assertions generated for typed templates, hence the manual loops.
+    #[doc(hidden)]
+    pub const fn accepts(fields: &[(&str, FieldShape)], key: &str, section: bool) -> bool {
+        let mut i = 0;
+        while i < fields.len() {
+            if str_eq(fields[i].0, key) {
+                return match fields[i].1 {
+                    FieldShape::Scalar => !section,
+                    FieldShape::List | FieldShape::Hash => section,
+                    FieldShape::Bool | FieldShape::Option | FieldShape::Unknown => true,
+                };
+            }
+            i += 1;
+        }
+        false
+    }
+
+    // Like `accepts`, but for a type whose fields may not be known, which
+    // may have any field
+    #[doc(hidden)]
+    pub const fn may_accept(fields: Option<&[(&str, FieldShape)]>,
+                            key: &str,
+                            section: bool)
+                            -> bool {
+        match fields {
+            Some(fields) => FieldShape::accepts(fields, key, section),
+            None => true,
+        }
+    }
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use typed::{Fields, FieldShape, FieldsOf, NoFields};
+
+    const FIELDS: &'static [(&'static str, FieldShape)] = &[("name", FieldShape::Scalar),
+                                                            ("cards", FieldShape::List),
+                                                            ("title", FieldShape::Option)];
+
+    #[test]
+    fn test_accepts() {
+        assert!(FieldShape::accepts(FIELDS, "name", false));
+        assert!(!FieldShape::accepts(FIELDS, "name", true));
+        assert!(FieldShape::accepts(FIELDS, "cards", true));
+        assert!(!FieldShape::accepts(FIELDS, "cards", false));
+        assert!(FieldShape::accepts(FIELDS, "title", true));
+        assert!(FieldShape::accepts(FIELDS, "title", false));
+        assert!(!FieldShape::accepts(FIELDS, "missing", false));
+        assert!(!FieldShape::accepts(FIELDS, "nam", false));
+    }
+
+    struct Hero;
+
+    impl Fields for Hero {
+        const FIELDS: &'static [(&'static str, FieldShape)] = FIELDS;
+    }
+
+    #[test]
+    fn test_may_accept() {
+        assert!(FieldShape::may_accept(FieldsOf::<Hero>::FIELDS, "name", false));
+        assert!(!FieldShape::may_accept(FieldsOf::<Hero>::FIELDS, "missing", false));
+        assert!(FieldShape::may_accept(FieldsOf::<String>::FIELDS, "missing", true));
+    }
+}
@@ -0,0 +1,21 @@
+// Helpers shared by the integration tests.  Each test file only uses some
+// of them.
+#![allow(dead_code)]
+
+use std::io::Cursor;
+
+use rustache::{Render, Renderer};
+
+// renders a template with the data and returns the output
+pub fn render<R: Render + ?Sized>(data: &R, template: &str) -> String {
+    let mut rv = Cursor::new(Vec::new());
+    data.render(template, &mut rv).unwrap();
+    String::from_utf8(rv.into_inner()).unwrap()
+}
+
+// renders a template with the data and the options of a renderer
+pub fn render_with<R: Render + ?Sized>(renderer: &Renderer, data: &R, template: &str) -> String {
+    let mut rv = Cursor::new(Vec::new());
+    renderer.render(data, template, &mut rv).unwrap();
+    String::from_utf8(rv.into_inner()).unwrap()
+}
@@ -0,0 +1,111 @@
+extern crate rustache;
+
+mod common;
+
+use std::collections::{BTreeMap, HashMap};
+
+use rustache::Context;
+
+// any context can be rendered in place
+fn render(data: &dyn Context, template: &str) -> String {
+    common::render(data, template)
+}
+
+struct Order {
+    id: u64,
+    customer: Option<String>,
+    lines: Vec<Line>,
+}
+
+struct Line {
+    item: &'static str,
+    quantity: u32,
+}
+
+impl Context for Order {
+    fn lookup(&self, key: &str) -> Option<&dyn Context> {
+        match key {
+            "id" => Some(&self.id),
+            "customer" => Some(&self.customer),
+            "lines" => Some(&self.lines),
+            _ => None,
+        }
+    }
+}
+
+impl Context for Line {
+    fn lookup(&self, key: &str) -> Option<&dyn Context> {
+        match key {
+            "item" => Some(&self.item),
+            "quantity" => Some(&self.quantity),
+            _ => None,
+        }
+    }
+}
+
+fn order() -> Order {
+    Order {
+        id: 12,
+        customer: None,
+        lines: vec![Line {
+                        item: "Lightwell",
+                        quantity: 2,
+                    },
+                    Line {
+                        item: "Velen",
+                        quantity: 1,
+                    }],
+    }
+}
+
+#[test]
+fn test_context_user_type() {
+    assert_eq!("#12: Lightwell x2, Velen x1, ",
+               render(&order(),
+                      "#{{ id }}: {{#lines}}{{ item }} x{{ quantity }}, {{/lines}}"));
+}
+
+#[test]
+fn test_context_option() {
+    let mut order = order();
+
+    assert_eq!("guest", render(&order, "{{#customer}}{{customer}}{{/customer}}{{^customer}}guest{{/customer}}"));
+
+    order.customer = Some("Jaina".to_string());
+    assert_eq!("Jaina", render(&order, "{{#customer}}{{customer}}{{/customer}}{{^customer}}guest{{/customer}}"));
+}
+
+#[test]
+fn test_context_empty_list_is_falsy() {
+    let mut order = order();
+    order.lines.clear();
+
+    assert_eq!("empty", render(&order, "{{#lines}}line{{/lines}}{{^lines}}empty{{/lines}}"));
+}
+
+#[test]
+fn test_context_std_maps() {
+    let mut hash = HashMap::new();
+    hash.insert("name".to_string(), "Anduin");
+    let mut tree = BTreeMap::new();
+    tree.insert("name".to_string(), true);
+
+    assert_eq!("Anduin", render(&hash, "{{ name }}"));
+    assert_eq!("yes", render(&tree, "{{#name}}yes{{/name}}"));
+}
+
+#[test]
+fn test_context_nested_maps() {
+    let mut inner = HashMap::new();
+    inner.insert("level".to_string(), 10);
+    let mut outer = HashMap::new();
+    outer.insert("stats".to_string(), inner);
+
+    assert_eq!("10 10", render(&outer, "{{ stats.level }} {{#stats}}{{ level }}{{/stats}}"));
+}
+
+#[test]
+fn test_context_plain_values() {
+    assert_eq!("", render(&"text", "{{ name }}"));
+    assert_eq!("static", render(&5u8, "static"));
+}
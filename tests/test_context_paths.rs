@@ -0,0 +1,104 @@
+extern crate rustache;
+
+mod common;
+
+use rustache::{HashBuilder, Renderer, VecBuilder};
+
+use common::{render, render_with};
+
+fn shop<'a>() -> HashBuilder<'a> {
+    HashBuilder::new()
+        .insert("name", "Goldshire Goods")
+        .insert("currency", "USD")
+        .insert("orders", VecBuilder::new()
+            .push(HashBuilder::new()
+                .insert("id", 1)
+                .insert("currency", "EUR")
+                .insert("items", VecBuilder::new()
+                    .push(HashBuilder::new()
+                        .insert("name", "Hearthstone")
+                        .insert("currency", "gold")
+                        .insert("rare", true))
+                    .push(HashBuilder::new().insert("name", "Mana Potion"))))
+            .push(HashBuilder::new()
+                .insert("id", 2)
+                .insert("currency", "GBP")
+                .insert("items", VecBuilder::new()
+                    .push(HashBuilder::new().insert("name", "Linen Cloth")))))
+}
+
+#[test]
+fn test_context_paths_nested_lists() {
+    let renderer = Renderer::new().enable_context_paths();
+
+    assert_eq!("1: Hearthstone gold/EUR/USD, Mana Potion EUR/EUR/USD, \
+                2: Linen Cloth GBP/GBP/USD, ",
+               render_with(&renderer,
+                           &shop(),
+                           "{{#orders}}{{id}}: {{#items}}{{name}} {{currency}}/{{../currency}}/\
+                            {{@root.currency}}, {{/items}}{{/orders}}"));
+}
+
+#[test]
+fn test_context_paths_several_levels() {
+    let renderer = Renderer::new().enable_context_paths();
+
+    assert_eq!("Hearthstone at Goldshire Goods #1;",
+               render_with(&renderer,
+                           &shop(),
+                           "{{#orders}}{{#items}}{{#rare}}{{name}} at {{../../name}} \
+                            #{{../id}};{{/rare}}{{/items}}{{/orders}}"));
+    assert_eq!("", render_with(&renderer, &shop(), "{{../../name}}{{#orders}}{{../../../id}}{{/orders}}"));
+}
+
+#[test]
+fn test_context_paths_hash_sections() {
+    let data = HashBuilder::new()
+        .insert("name", "Stormwind")
+        .insert("order", HashBuilder::new()
+            .insert("name", "Order 7")
+            .insert("currency", "EUR")
+            .insert("customer", HashBuilder::new().insert("name", "Jaina").insert("title", "Archmage"))
+            .insert("items", VecBuilder::new()
+                .push(HashBuilder::new().insert("name", "Mana Potion").insert("currency", "gold"))));
+    let renderer = Renderer::new().enable_context_paths();
+
+    assert_eq!("Archmage / Order 7 / Stormwind",
+               render_with(&renderer,
+                           &data,
+                           "{{#order}}{{#customer}}{{title}} / {{../name}} / {{@root.name}}\
+                            {{/customer}}{{/order}}"));
+    assert_eq!("Mana Potion: gold, EUR",
+               render_with(&renderer,
+                           &data,
+                           "{{#order}}{{#items}}{{name}}: {{currency}}, {{../currency}}{{/items}}{{/order}}"));
+}
+
+#[test]
+fn test_context_paths_dotted_names_and_sections() {
+    let data = shop().insert("owner", HashBuilder::new().insert("name", "Tomas"));
+    let renderer = Renderer::new().enable_context_paths();
+
+    assert_eq!("1 Tomas; 2 Tomas; ",
+               render_with(&renderer,
+                           &data,
+                           "{{#orders}}{{id}} {{#@root.owner}}{{name}}{{/@root.owner}}; {{/orders}}"));
+    assert_eq!("1 none 2 none ",
+               render_with(&renderer,
+                           &data,
+                           "{{#orders}}{{id}} {{^../missing}}none {{/../missing}}{{/orders}}"));
+    assert_eq!("Tomas", render_with(&renderer, &data, "{{@root.owner.name}}"));
+}
+
+#[test]
+fn test_context_paths_off_by_default() {
+    let data = shop();
+    let template = "{{#orders}}{{../currency}}{{@root.currency}}{{/orders}}";
+
+    assert_eq!("", render(&data, template));
+    assert_eq!("", render_with(&Renderer::new(), &data, template));
+    assert_eq!("USD",
+               render_with(&Renderer::new(),
+                           &HashBuilder::new().insert("@root", HashBuilder::new().insert("currency", "USD")),
+                           "{{@root.currency}}"));
+}
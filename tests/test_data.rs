@@ -0,0 +1,166 @@
+extern crate rustache;
+
+mod common;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rustache::{Data, HashBuilder, SharedLambda, VecBuilder};
+
+use common::render;
+
+#[test]
+fn test_data_wide_integers() {
+    let data = HashBuilder::new()
+        .insert("id", 9007199254740993i64)
+        .insert("min", i64::MIN)
+        .insert("bytes", u64::MAX)
+        .insert("count", 3usize);
+
+    assert_eq!("9007199254740993 -9223372036854775808 18446744073709551615 3",
+               render(&data, "{{ id }} {{ min }} {{ bytes }} {{ count }}"));
+}
+
+#[test]
+fn test_data_unsigned_only_when_needed() {
+    assert_eq!(Data::Integer(7), Data::from(7u8));
+    assert_eq!(Data::Integer(i64::MAX), Data::from(i64::MAX as u64));
+    assert_eq!(Data::Unsigned(u64::MAX), Data::from(u64::MAX));
+    assert_eq!(Data::Integer(7), Data::Unsigned(7));
+    assert!(Data::Integer(-1) != Data::Unsigned(u64::MAX));
+}
+
+#[test]
+fn test_data_numbers_are_truthy() {
+    let data = HashBuilder::new()
+        .insert("zero", 0u32)
+        .insert("big", u64::MAX);
+
+    assert_eq!("zero big", render(&data, "{{#zero}}zero{{/zero}} {{#big}}big{{/big}}"));
+}
+
+#[test]
+fn test_data_from_option() {
+    assert_eq!(Data::Null, Data::from(None::<i32>));
+    assert_eq!(Data::from("x"), Data::from(Some("x")));
+}
+
+#[test]
+fn test_data_null_interpolation() {
+    let data = HashBuilder::new().insert("title", None::<String>);
+
+    assert_eq!("<>", render(&data, "<{{ title }}{{{ title }}}>"));
+}
+
+#[test]
+fn test_data_null_truthiness() {
+    let data = HashBuilder::new()
+        .insert("title", None::<String>)
+        .insert("name", Some("Anduin"));
+
+    assert_eq!("untitled Anduin",
+               render(&data,
+                      "{{#title}}titled{{/title}}{{^title}}untitled{{/title}} \
+                       {{#name}}{{name}}{{/name}}"));
+}
+
+#[test]
+fn test_data_null_in_vector() {
+    let data = HashBuilder::new()
+        .insert("values", VecBuilder::new().push(1).push(None::<i32>).push(2));
+
+    assert_eq!("12", render(&data, "{{ values }}"));
+}
+
+#[test]
+fn test_data_lambdas_compare_by_identity() {
+    let shout: SharedLambda = Arc::new(|text: String| text.to_uppercase());
+    let other: SharedLambda = Arc::new(|text: String| text.to_uppercase());
+
+    assert_eq!(Data::Lambda(shout.clone()), Data::Lambda(shout.clone()));
+    assert!(Data::Lambda(shout) != Data::Lambda(other));
+}
+
+#[test]
+fn test_data_clone_shares_lambdas() {
+    let data = HashBuilder::new()
+        .insert("name", "Anduin")
+        .insert_fn("shout", |text| text.to_uppercase());
+    let copy = data.clone();
+
+    assert_eq!(Data::from(data), Data::from(copy.clone()));
+    assert_eq!("HI Anduin", render(&copy, "{{#shout}}hi{{/shout}} {{ name }}"));
+}
+
+#[test]
+fn test_data_clone_lazy() {
+    let data = Data::from(HashBuilder::new().insert_lazy("cards", || 3));
+
+    assert_eq!(data, data.clone());
+}
+
+#[test]
+fn test_data_clone_shares_lazy() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let data = HashBuilder::new().insert_lazy("cards", move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+        3
+    });
+    let copy = data.clone();
+    assert_eq!(0, calls.load(Ordering::SeqCst));
+
+    assert_eq!("3", render(&data, "{{ cards }}"));
+    assert_eq!("3", render(&copy, "{{ cards }}"));
+    assert_eq!(1, calls.load(Ordering::SeqCst));
+}
+
+#[test]
+fn test_data_clone_shares_stream() {
+    let data = HashBuilder::new().insert_stream("cards", vec!["Heal", "Smite"]);
+    let copy = data.clone();
+
+    assert_eq!("HealSmite", render(&data, "{{ cards }}"));
+    assert_eq!("", render(&copy, "{{ cards }}"));
+}
+
+#[test]
+fn test_data_patch_builder() {
+    let mut data = HashBuilder::new()
+        .insert("form", HashBuilder::new().insert("action", "/login"))
+        .insert("errors", VecBuilder::new().push("too short"));
+
+    *data.get_path_mut("form.action").unwrap() = Data::from("/signin");
+    data.set("csrf_token", "4b1d");
+    data.entry("title").or_insert_with(|| Data::from("Sign in"));
+    data.remove("errors");
+
+    assert_eq!("Sign in /signin 4b1d ",
+               render(&data, "{{ title }} {{ form.action }} {{ csrf_token }} {{ errors }}"));
+}
+
+#[test]
+fn test_data_collect_builders() {
+    let mut classes: VecBuilder = vec!["Mage", "Druid"].into_iter().collect();
+    classes.extend(vec!["Priest"]);
+    let data: HashBuilder = vec![("game", Data::from("Hearthstone")),
+                                 ("classes", Data::from(classes))]
+        .into_iter()
+        .collect();
+
+    assert_eq!(Some(&Data::from("Priest")), data.get_path("classes.2"));
+    assert_eq!("Hearthstone MageDruidPriest", render(&data, "{{ game }} {{ classes }}"));
+}
+
+#[test]
+fn test_data_hashes_keep_insertion_order() {
+    let mut data = HashBuilder::new()
+        .insert("volume", 11)
+        .insert("difficulty", "heroic")
+        .insert("mode", "Arena");
+    data.remove("difficulty");
+    data.set("difficulty", "normal");
+    data.set("volume", 7);
+
+    assert_eq!("\"volume\": 7\n\"mode\": \"Arena\"\n\"difficulty\": \"normal\"\n", data.dump());
+}
@@ -0,0 +1,126 @@
+extern crate rustache;
+#[macro_use]
+extern crate rustache_derive;
+
+mod common;
+
+use rustache::{Data, HashBuilder, VecBuilder};
+
+use common::render;
+
+#[derive(Mustache)]
+struct Card {
+    name: String,
+    cost: i32,
+}
+
+#[derive(Mustache)]
+struct Hero {
+    name: &'static str,
+    #[mustache(rename = "class")]
+    class_name: String,
+    cards: Vec<Card>,
+    title: Option<String>,
+    #[mustache(skip)]
+    #[allow(dead_code)]
+    secret: String,
+}
+
+#[derive(Mustache)]
+enum Rarity {
+    Common,
+    #[mustache(rename = "legendary")]
+    Legendary,
+    Golden(i32),
+    Crafted { dust: i32 },
+}
+
+#[derive(Mustache)]
+struct Wrapper<T> {
+    inner: T,
+}
+
+fn hero() -> Hero {
+    Hero {
+        name: "Anduin",
+        class_name: "Priest".to_string(),
+        cards: vec![Card { name: "Prophet Velen".to_string(), cost: 7 },
+                    Card { name: "Lightwell".to_string(), cost: 2 }],
+        title: None,
+        secret: "Shadowform".to_string(),
+    }
+}
+
+#[test]
+fn test_derive_struct_fields() {
+    let data = HashBuilder::from(hero());
+
+    assert_eq!("Anduin the Priest".to_string(),
+               render(&data, "{{name}} the {{class}}"));
+}
+
+#[test]
+fn test_derive_struct_matches_builder() {
+    let expected: Data = HashBuilder::new()
+        .insert("name", "Anduin")
+        .insert("class", "Priest")
+        .insert("cards",
+                VecBuilder::new()
+                    .push(HashBuilder::new().insert("name", "Prophet Velen").insert("cost", 7))
+                    .push(HashBuilder::new().insert("name", "Lightwell").insert("cost", 2)))
+        .insert("title", None::<String>)
+        .into();
+
+    assert_eq!(expected, Data::from(hero()));
+}
+
+#[test]
+fn test_derive_skipped_field() {
+    let data = HashBuilder::from(hero());
+
+    assert_eq!("[]".to_string(), render(&data, "[{{secret}}]"));
+}
+
+#[test]
+fn test_derive_nested_vec() {
+    let data = HashBuilder::from(hero());
+
+    assert_eq!("<Prophet Velen:7><Lightwell:2>".to_string(),
+               render(&data, "{{#cards}}<{{name}}:{{cost}}>{{/cards}}"));
+}
+
+#[test]
+fn test_derive_option_none_is_falsy() {
+    let data = HashBuilder::from(hero());
+
+    assert_eq!("Anduin".to_string(),
+               render(&data, "{{name}}{{#title}}, {{title}}{{/title}}"));
+}
+
+#[test]
+fn test_derive_option_some() {
+    let mut anduin = hero();
+    anduin.title = Some("King of Stormwind".to_string());
+    let data = HashBuilder::from(anduin);
+
+    assert_eq!("Anduin, King of Stormwind".to_string(),
+               render(&data, "{{name}}{{#title}}, {{title}}{{/title}}"));
+}
+
+#[test]
+fn test_derive_enum_variants() {
+    assert_eq!(Data::from("Common"), Data::from(Rarity::Common));
+    assert_eq!(Data::from("legendary"), Data::from(Rarity::Legendary));
+    assert_eq!(Data::from(HashBuilder::new().insert("Golden", 3)),
+               Data::from(Rarity::Golden(3)));
+    assert_eq!(Data::from(HashBuilder::new()
+                   .insert("Crafted", HashBuilder::new().insert("dust", 400))),
+               Data::from(Rarity::Crafted { dust: 400 }));
+}
+
+#[test]
+fn test_derive_generic_struct() {
+    let data = HashBuilder::from(Wrapper { inner: Wrapper { inner: 42 } });
+
+    assert_eq!("42".to_string(), render(&data, "{{#inner}}{{inner}}{{/inner}}"));
+}
@@ -0,0 +1,122 @@
+extern crate rustache;
+
+mod common;
+
+use std::io::{Cursor, Write};
+
+use rustache::{Context, ErrorKind, HashBuilder, Render, Renderer, Result, VecBuilder};
+
+use common::{render, render_with};
+
+fn hero<'a>() -> HashBuilder<'a> {
+    HashBuilder::new()
+        .insert("name", "anduin wrynn")
+        .insert("title", "")
+        .insert("gold", 1234567)
+        .insert("cards", VecBuilder::new().push("Heal").push("Smite").push("Holy Nova"))
+        .insert("hero", HashBuilder::new().insert("class", "<Priest>"))
+}
+
+#[test]
+fn test_filters_chain() {
+    let renderer = Renderer::new().enable_filters();
+
+    assert_eq!("ANDUIN...",
+               render_with(&renderer, &hero(), "{{ name | upper | truncate:6 }}"));
+    assert_eq!("Anduin wrynn has 1,234,567.00 gold",
+               render_with(&renderer, &hero(), "{{ name | capitalize }} has {{ gold | number:2 }} gold"));
+}
+
+#[test]
+fn test_filters_lists() {
+    let renderer = Renderer::new().enable_filters();
+
+    assert_eq!("3 cards: Heal / Smite / Holy Nova",
+               render_with(&renderer, &hero(), r#"{{ cards | length }} cards: {{ cards | join:" / " }}"#));
+    assert_eq!("Heal | Smite | Holy Nova",
+               render_with(&renderer, &hero(), r#"{{ cards | join:" | " }}"#));
+    assert_eq!("HEAL | SMITE | HOLY NOVA",
+               render_with(&renderer, &hero(), r#"{{ cards | join:" | " | upper }}"#));
+}
+
+#[test]
+fn test_filters_default() {
+    let renderer = Renderer::new().enable_filters();
+
+    assert_eq!("Hero, Stranger",
+               render_with(&renderer, &hero(), "{{ title | default:Hero }}, {{ missing | default:Stranger }}"));
+}
+
+#[test]
+fn test_filters_dotted_names_and_escaping() {
+    let renderer = Renderer::new().enable_filters();
+
+    assert_eq!("&lt;priest&gt; <priest>",
+               render_with(&renderer, &hero(), "{{ hero.class | lower }} {{{ hero.class | lower }}}"));
+    assert_eq!("Heal Smite Holy nova ",
+               render_with(&renderer,
+                           &HashBuilder::new().insert("cards", VecBuilder::new()
+                               .push(HashBuilder::new().insert("name", "heal"))
+                               .push(HashBuilder::new().insert("name", "smite"))
+                               .push(HashBuilder::new().insert("name", "holy nova"))),
+                           "{{#cards}}{{ name | capitalize }} {{/cards}}"));
+}
+
+#[test]
+fn test_filters_custom() {
+    let renderer = Renderer::new()
+        .enable_filters()
+        .filter("upper", |_: Option<&dyn Context>, _: Option<&str>| "shadowed")
+        .filter("repeat", |value: Option<&dyn Context>, arg: Option<&str>| {
+            let text = value.and_then(|value| value.interpolate()).unwrap_or_default();
+            text.repeat(arg.and_then(|arg| arg.parse().ok()).unwrap_or(1))
+        });
+
+    assert_eq!("shadowed anduin wrynnanduin wrynn",
+               render_with(&renderer, &hero(), "{{ name | upper }} {{ name | repeat:2 }}"));
+}
+
+#[test]
+fn test_filters_unknown() {
+    let renderer = Renderer::new().enable_filters();
+
+    // the error isn't lost when the tag is inside a list or another section
+    for template in &["{{ name | shout }}",
+                      "{{#cards}}{{. | shout}}x{{/cards}}",
+                      "{{#hero}}{{ class | shout }}x{{/hero}}"] {
+        let mut rv = Cursor::new(Vec::new());
+        match renderer.render(&hero(), template, &mut rv) {
+            Err(err) => {
+                match *err.kind() {
+                    ErrorKind::UnknownFilter(ref name) => assert_eq!("shout", name),
+                    _ => panic!("unexpected error: {}", err),
+                }
+            }
+            Ok(_) => panic!("an unknown filter should fail in {}", template),
+        }
+    }
+}
+
+#[test]
+fn test_filters_off_by_default() {
+    let data = hero().insert("name | upper", "looked up");
+
+    assert_eq!("looked up", render(&data, "{{ name | upper }}"));
+    assert_eq!("looked up", render_with(&Renderer::new(), &data, "{{ name | upper }}"));
+}
+
+// a type implementing only `render`, as types did before renderers
+struct Greeting;
+
+impl Render for Greeting {
+    fn render<W: Write>(&self, template: &str, writer: &mut W) -> Result<()> {
+        HashBuilder::new().insert("name", "Thrall").render(template, writer)
+    }
+}
+
+#[test]
+fn test_filters_render_without_options() {
+    let renderer = Renderer::new().enable_filters();
+
+    assert_eq!("Thrall", render_with(&renderer, &Greeting, "{{ name }}"));
+}
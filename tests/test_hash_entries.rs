@@ -0,0 +1,62 @@
+extern crate rustache;
+
+mod common;
+
+use rustache::{HashBuilder, Renderer};
+
+use common::{render, render_with};
+
+fn settings<'a>() -> HashBuilder<'a> {
+    HashBuilder::new()
+        .insert("settings", HashBuilder::new()
+            .insert("volume", 11)
+            .insert("difficulty", "heroic")
+            .insert("mode", HashBuilder::new().insert("label", "Arena")))
+        .insert("empty", HashBuilder::new())
+}
+
+#[test]
+fn test_hash_entries_in_order() {
+    let renderer = Renderer::new().enable_hash_iteration();
+
+    assert_eq!("volume: 11; difficulty: heroic; mode: Arena; ",
+               render_with(&renderer,
+                           &settings(),
+                           "{{#settings.@entries}}{{@key}}: {{@value}}{{label}}; {{/settings.@entries}}"));
+}
+
+#[test]
+fn test_hash_entries_empty() {
+    let renderer = Renderer::new().enable_hash_iteration();
+
+    assert_eq!("none",
+               render_with(&renderer,
+                           &settings(),
+                           "{{#empty.@entries}}{{@key}}{{/empty.@entries}}{{^empty.@entries}}none{{/empty.@entries}}"));
+}
+
+#[test]
+fn test_hash_entries_nested_in_section() {
+    let renderer = Renderer::new().enable_hash_iteration();
+    let data = HashBuilder::new().insert("page", settings());
+
+    assert_eq!("volume difficulty mode ",
+               render_with(&renderer,
+                           &data,
+                           "{{#page}}{{#settings.@entries}}{{@key}} {{/settings.@entries}}{{/page}}"));
+}
+
+#[test]
+fn test_hash_entries_with_loop_variables() {
+    let renderer = Renderer::new().enable_hash_iteration().enable_loop_variables();
+
+    assert_eq!("volume, difficulty, mode",
+               render_with(&renderer,
+                           &settings(),
+                           "{{#settings.@entries}}{{@key}}{{^@last}}, {{/@last}}{{/settings.@entries}}"));
+}
+
+#[test]
+fn test_hash_entries_off_by_default() {
+    assert_eq!("", render(&settings(), "{{#settings.@entries}}{{@key}}{{/settings.@entries}}"));
+}
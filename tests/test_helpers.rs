@@ -0,0 +1,57 @@
+extern crate rustache;
+extern crate rustc_serialize;
+
+mod common;
+
+use rustc_serialize::json::Json;
+use rustache::{HashBuilder, LambdaContext, Layers, Renderer};
+
+use common::render_with;
+
+fn renderer() -> Renderer {
+    Renderer::new()
+        .helper("uppercase", |text: &str, context: &mut LambdaContext| {
+            context.render(text).unwrap().to_uppercase()
+        })
+        .helper("i18n", |text: &str, context: &mut LambdaContext| {
+            let greeting = match context.lookup("locale").and_then(|locale| locale.interpolate()) {
+                Some(ref locale) if locale == "fr" => "Bonjour",
+                _ => "Hello",
+            };
+            format!("{} {}", greeting, context.render(text).unwrap())
+        })
+        .helper("version", |_: &str, _: &mut LambdaContext| "<1.0>".to_string())
+}
+
+fn render_json(json: &str, template: &str) -> String {
+    render_with(&renderer(), &Json::from_str(json).unwrap(), template)
+}
+
+#[test]
+fn test_helpers_from_json() {
+    assert_eq!("ANDUIN and Jaina",
+               render_json(r#"{"name": "Anduin", "friend": "Jaina"}"#,
+                           "{{#uppercase}}{{name}}{{/uppercase}} and {{friend}}"));
+}
+
+#[test]
+fn test_helpers_in_lists() {
+    assert_eq!("Bonjour Anduin. Bonjour Jaina. ",
+               render_json(r#"{"locale": "fr", "heroes": [{"name": "Anduin"}, {"name": "Jaina"}]}"#,
+                           "{{#heroes}}{{#i18n}}{{name}}.{{/i18n}} {{/heroes}}"));
+}
+
+#[test]
+fn test_helpers_interpolation() {
+    assert_eq!("&lt;1.0&gt; <1.0>", render_json("{}", "{{version}} {{{version}}}"));
+}
+
+#[test]
+fn test_helpers_data_first() {
+    assert_eq!("2.0", render_json(r#"{"version": "2.0"}"#, "{{version}}"));
+
+    let site = HashBuilder::new().insert("version", "3.0");
+    let page = HashBuilder::new();
+
+    assert_eq!("3.0", render_with(&renderer(), &Layers::new().push(&site).push(&page), "{{version}}"));
+}
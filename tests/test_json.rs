@@ -0,0 +1,65 @@
+extern crate rustache;
+extern crate rustc_serialize;
+
+mod common;
+
+use std::io::Cursor;
+
+use rustache::Render;
+use rustc_serialize::json::Json;
+
+use common::render;
+
+#[test]
+fn test_json_numbers() {
+    let data = Json::from_str(r#"{"age": 42, "height": 1.5, "id": 9007199254740993}"#).unwrap();
+
+    assert_eq!("42 1.5 9007199254740993",
+               render(&data, "{{ age }} {{ height }} {{ id }}"));
+}
+
+#[test]
+fn test_json_numeric_array() {
+    let data = Json::from_str(r#"{"matrix": [[1, 2], [3.5, null]]}"#).unwrap();
+
+    assert_eq!("<123.5>", render(&data, "<{{ matrix }}>"));
+}
+
+#[test]
+fn test_json_null() {
+    let data = Json::from_str(r#"{"name": null}"#).unwrap();
+
+    assert_eq!("<> nameless",
+               render(&data, "<{{ name }}> {{#name}}named{{/name}}{{^name}}nameless{{/name}}"));
+}
+
+#[test]
+fn test_json_array_root() {
+    let data = Json::from_str(r#"[{"name": "a"}, {"name": "b"}]"#).unwrap();
+
+    assert_eq!("<>", render(&data, "<{{ name }}>"));
+    assert_eq!("<a b >", render(&data, "<{{#.}}{{ name }} {{/.}}>"));
+}
+
+#[test]
+fn test_json_scalar_root() {
+    let data = Json::from_str("5").unwrap();
+
+    assert_eq!("<5>", render(&data, "<{{ name }}{{.}}>"));
+    assert_eq!("<5!>", render(&data, "<{{#.}}{{.}}!{{/.}}>"));
+}
+
+#[test]
+fn test_json_from_string() {
+    let data = r#"{"name": "Bob"}"#.to_string();
+
+    assert_eq!("Bob", render(&data as &dyn ToString, "{{ name }}"));
+}
+
+#[test]
+fn test_json_invalid_string() {
+    let data = "{".to_string();
+    let mut rv = Cursor::new(Vec::new());
+
+    assert!((&data as &dyn ToString).render("{{ name }}", &mut rv).is_err());
+}
@@ -0,0 +1,182 @@
+extern crate rustache;
+
+mod common;
+
+use rustache::{Context, HashBuilder, VecBuilder};
+
+use common::render;
+
+fn users<'a>() -> VecBuilder<'a> {
+    VecBuilder::new()
+        .push(HashBuilder::new().insert("id", 1).insert("name", "Anduin"))
+        .push(HashBuilder::new().insert("id", 2).insert("name", "Jaina"))
+}
+
+#[test]
+fn test_context_lambda_renders_in_loop() {
+    let data = HashBuilder::new()
+        .insert("users", users())
+        .insert_context_lambda("link", |text, context| {
+            let url = context.render(text).unwrap();
+            format!("[{}]", url)
+        });
+
+    assert_eq!("[/users/1][/users/2]",
+               render(&data, "{{#users}}{{#link}}/users/{{id}}{{/link}}{{/users}}"));
+}
+
+#[test]
+fn test_context_lambda_renders_in_section() {
+    let data = HashBuilder::new()
+        .insert("user", HashBuilder::new().insert("name", "Anduin"))
+        .insert_context_lambda("shout", |text, context| {
+            context.render(text).unwrap().to_uppercase()
+        });
+
+    assert_eq!("HELLO ANDUIN",
+               render(&data, "{{#user}}{{#shout}}hello {{name}}{{/shout}}{{/user}}"));
+}
+
+#[test]
+fn test_context_lambda_lookup() {
+    let data = HashBuilder::new()
+        .insert("users", users())
+        .insert("site", HashBuilder::new().insert("host", "example.com"))
+        .insert_context_lambda("url", |_, context| {
+            let host = context.lookup("site.host").and_then(|host| host.interpolate());
+            let id = context.lookup("id").and_then(|id| id.interpolate());
+            format!("https://{}/users/{} ",
+                    host.unwrap_or_default(),
+                    id.unwrap_or_default())
+        });
+
+    assert_eq!("https://example.com/users/1 https://example.com/users/2 ",
+               render(&data, "{{#users}}{{url}}{{/users}}"));
+}
+
+#[test]
+fn test_context_lambda_interpolation_is_escaped() {
+    let data = HashBuilder::new()
+        .insert("name", "Anduin")
+        .insert_context_lambda("bold", |_, context| context.render("<b>{{name}}</b>").unwrap());
+
+    assert_eq!("&lt;b&gt;Anduin&lt;/b&gt; <b>Anduin</b>",
+               render(&data, "{{bold}} {{{bold}}}"));
+}
+
+#[test]
+fn test_list_items_see_outer_names() {
+    let data = HashBuilder::new()
+        .insert("greeting", "Hi")
+        .insert("users", users());
+
+    assert_eq!("Hi Anduin. Hi Jaina. ",
+               render(&data, "{{#users}}{{greeting}} {{name}}. {{/users}}"));
+}
+
+#[test]
+fn test_list_items_see_names_of_enclosing_sections() {
+    let data = HashBuilder::new()
+        .insert("user",
+                HashBuilder::new()
+                    .insert("uname", "anduin")
+                    .insert("tags", VecBuilder::new().push("a").push("b")))
+        .insert_context_lambda("tag", |text, context| {
+            let uname = context.lookup("uname")
+                .and_then(|uname| uname.interpolate())
+                .unwrap_or_default()
+                .into_owned();
+            format!("{}#{}", uname, context.render(text).unwrap())
+        });
+
+    assert_eq!("anduin:a anduin:b ",
+               render(&data, "{{#user}}{{#tags}}{{uname}}:{{.}} {{/tags}}{{/user}}"));
+    assert_eq!("anduin#a anduin#b ",
+               render(&data, "{{#user}}{{#tags}}{{#tag}}{{.}}{{/tag}} {{/tags}}{{/user}}"));
+}
+
+#[test]
+fn test_data_lambda_section_sees_names_of_enclosing_sections() {
+    let data = HashBuilder::new()
+        .insert("user",
+                HashBuilder::new()
+                    .insert("uname", "anduin")
+                    .insert_data_lambda("profile", |_| HashBuilder::new().insert("level", 60)))
+        .insert("uname", "outer");
+
+    assert_eq!("anduin 60",
+               render(&data, "{{#user}}{{#profile}}{{uname}} {{level}}{{/profile}}{{/user}}"));
+}
+
+fn deck<'a>() -> HashBuilder<'a> {
+    HashBuilder::new()
+        .insert("cards",
+                VecBuilder::new()
+                    .push(HashBuilder::new().insert("name", "Heal").insert("cost", 1))
+                    .push(HashBuilder::new().insert("name", "Smite").insert("cost", 2))
+                    .push(HashBuilder::new().insert("name", "Mind Control").insert("cost", 10)))
+        .insert("max_cost", 2)
+        .insert_data_lambda("cheap", |context| {
+            let max = context.lookup("max_cost")
+                .and_then(|max| max.interpolate())
+                .and_then(|max| max.parse::<i64>().ok())
+                .unwrap_or(0);
+            let cards = context.lookup("cards").and_then(|cards| cards.items());
+            let mut rv = VecBuilder::new();
+            for card in cards.into_iter().flatten() {
+                let cost = card.lookup("cost")
+                    .and_then(|cost| cost.interpolate())
+                    .and_then(|cost| cost.parse::<i64>().ok())
+                    .unwrap_or(0);
+                if cost <= max {
+                    rv = rv.push(card.lookup("name").and_then(|name| name.interpolate()).unwrap_or_default().into_owned());
+                }
+            }
+            rv
+        })
+}
+
+#[test]
+fn test_data_lambda_list() {
+    assert_eq!("HealSmite", render(&deck(), "{{ cheap }}"));
+}
+
+#[test]
+fn test_data_lambda_truthiness() {
+    let data = HashBuilder::new()
+        .insert_data_lambda("empty", |_| VecBuilder::new())
+        .insert_data_lambda("yes", |_| true);
+
+    assert_eq!("none yes",
+               render(&data, "{{#empty}}some{{/empty}}{{^empty}}none{{/empty}} \
+                              {{#yes}}yes{{/yes}}{{^yes}}no{{/yes}}"));
+}
+
+#[test]
+fn test_data_lambda_hash_and_items() {
+    let data = HashBuilder::new()
+        .insert("name", "Anduin")
+        .insert_data_lambda("user", |context| {
+            let name = context.lookup("name").and_then(|name| name.interpolate()).unwrap_or_default();
+            HashBuilder::new().insert("greeting", format!("Hello {}", name))
+        })
+        .insert_data_lambda("pages", |_| {
+            VecBuilder::new()
+                .push(HashBuilder::new().insert("n", 1))
+                .push_data_lambda(|_| HashBuilder::new().insert("n", 2))
+        });
+
+    assert_eq!("Hello Anduin 12",
+               render(&data, "{{#user}}{{ greeting }}{{/user}} {{#pages}}{{n}}{{/pages}}"));
+}
+
+#[test]
+fn test_data_lambda_called_each_render() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let calls = AtomicUsize::new(0);
+    let data = HashBuilder::new()
+        .insert_data_lambda("count", |_| calls.fetch_add(1, Ordering::SeqCst) + 1);
+
+    assert_eq!("1 2", render(&data, "{{ count }} {{ count }}"));
+}
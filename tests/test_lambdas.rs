@@ -0,0 +1,43 @@
+extern crate rustache;
+
+use std::io::Cursor;
+
+use rustache::{HashBuilder, Render, VecBuilder};
+
+// A section lambda inside a list is called once for each item, and its
+// result is rendered with the item's data.
+#[test]
+fn test_lambdas_section_in_list() {
+    let mut calls = 0;
+    let mut f = |txt: String| {
+        calls += 1;
+        format!("[{}]", txt)
+    };
+    let data = HashBuilder::new()
+        .insert("items", VecBuilder::new()
+            .push(HashBuilder::new().insert("name", "a"))
+            .push(HashBuilder::new().insert("name", "b")))
+        .insert_lambda("lambda", &mut f);
+    let mut rv = Cursor::new(Vec::new());
+
+    data.render("{{#items}}{{#lambda}}{{name}}{{/lambda}}{{/items}}", &mut rv).unwrap();
+    drop(data);
+
+    assert_eq!("[a][b]".to_string(), String::from_utf8(rv.into_inner()).unwrap());
+    assert_eq!(2, calls);
+}
+
+// The tags an interpolation lambda returns are rendered before the result
+// is escaped.
+#[test]
+fn test_lambdas_interpolation_escaped_after_rendering() {
+    let mut f = |_| { "<{{planet}}>".to_string() };
+    let data = HashBuilder::new()
+        .insert("planet", "world")
+        .insert_lambda("lambda", &mut f);
+    let mut rv = Cursor::new(Vec::new());
+
+    data.render("{{lambda}} {{{lambda}}}", &mut rv).unwrap();
+
+    assert_eq!("&lt;world&gt; <world>".to_string(), String::from_utf8(rv.into_inner()).unwrap());
+}
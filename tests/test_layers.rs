@@ -0,0 +1,80 @@
+extern crate rustache;
+
+mod common;
+
+use std::collections::HashMap;
+
+use rustache::{Context, HashBuilder, Layers, VecBuilder};
+
+use common::render;
+
+#[test]
+fn test_layers_fall_through() {
+    let site = HashBuilder::new()
+        .insert("site", "Hearthstone")
+        .insert("title", "Home")
+        .insert("footer", "(c) Blizzard");
+    let page = HashBuilder::new().insert("title", "Decks");
+    let request = HashBuilder::new().insert("user", "Anduin");
+
+    let data = Layers::new().push(&site).push(&page).push(&request);
+
+    assert_eq!("Decks - Hearthstone for Anduin (c) Blizzard",
+               render(&data, "{{ title }} - {{ site }} for {{ user }} {{ footer }}"));
+}
+
+#[test]
+fn test_layers_sections() {
+    let site = HashBuilder::new()
+        .insert("admin", false)
+        .insert("nav", HashBuilder::new().insert("home", "/"));
+    let request = HashBuilder::new().insert("admin", true);
+
+    let data = Layers::new().push(&site).push(&request);
+
+    assert_eq!("admin /",
+               render(&data, "{{#admin}}admin{{/admin}}{{^admin}}guest{{/admin}} \
+                              {{#nav}}{{ home }}{{/nav}}"));
+}
+
+#[test]
+fn test_layers_inside_lists() {
+    let site = HashBuilder::new().insert("currency", "$");
+    let page = HashBuilder::new()
+        .insert("cards",
+                VecBuilder::new()
+                    .push(HashBuilder::new().insert("name", "Heal").insert("price", 1))
+                    .push(HashBuilder::new().insert("name", "Smite").insert("price", 2)));
+
+    let data = Layers::new().push(&site).push(&page);
+
+    assert_eq!("Heal $1 Smite $2 ",
+               render(&data, "{{#cards}}{{ name }} {{ currency }}{{ price }} {{/cards}}"));
+}
+
+#[test]
+fn test_layers_any_context() {
+    let mut site = HashMap::new();
+    site.insert("site".to_string(), "Hearthstone".to_string());
+    let page = HashBuilder::new().insert("title", "Decks");
+
+    let data = Layers::new().push(&site).push(&page);
+
+    assert_eq!("Decks - Hearthstone", render(&data, "{{ title }} - {{ site }}"));
+}
+
+#[test]
+fn test_layers_trait_objects() {
+    let site = HashBuilder::new().insert("site", "Hearthstone");
+    let page = HashBuilder::new().insert("title", "Decks");
+    let layers: Vec<&dyn Context> = vec![&site, &page];
+
+    let data = layers.iter().fold(Layers::new(), |data, layer| data.push(*layer));
+
+    assert_eq!("Decks - Hearthstone", render(&data, "{{ title }} - {{ site }}"));
+}
+
+#[test]
+fn test_layers_empty() {
+    assert_eq!("Hello ", render(&Layers::new(), "Hello {{ name }}"));
+}
@@ -0,0 +1,76 @@
+extern crate rustache;
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rustache::{Data, HashBuilder, VecBuilder};
+
+use common::render;
+
+#[test]
+fn test_lazy_value_is_computed_once() {
+    let calls = AtomicUsize::new(0);
+    let data = HashBuilder::new().insert_lazy("count", || {
+        calls.fetch_add(1, Ordering::SeqCst);
+        42
+    });
+
+    assert_eq!("42 42", render(&data, "{{ count }} {{ count }}"));
+    assert_eq!(1, calls.load(Ordering::SeqCst));
+}
+
+#[test]
+fn test_lazy_value_unused() {
+    let calls = AtomicUsize::new(0);
+    let data = HashBuilder::new()
+        .insert("admin", false)
+        .insert_lazy("count", || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            42
+        });
+
+    assert_eq!("", render(&data, "{{#admin}}{{ count }}{{/admin}}"));
+    assert_eq!(0, calls.load(Ordering::SeqCst));
+}
+
+#[test]
+fn test_lazy_section_data() {
+    let data = HashBuilder::new()
+        .insert_lazy("user", || HashBuilder::new().insert("name", "Anduin"))
+        .insert_lazy("cards", || VecBuilder::new().push(HashBuilder::new().insert("name", "Heal")))
+        .insert_lazy("empty", VecBuilder::new);
+
+    assert_eq!("Anduin Heal none",
+               render(&data,
+                      "{{#user}}{{ name }}{{/user}} {{#cards}}{{ name }}{{/cards}} \
+                       {{^empty}}none{{/empty}}"));
+}
+
+#[test]
+fn test_lazy_vector_items() {
+    let data = HashBuilder::new()
+        .insert("numbers", VecBuilder::new().push(1).push_lazy(|| 2).push(3));
+
+    assert_eq!("123", render(&data, "{{ numbers }}"));
+}
+
+#[test]
+fn test_lazy_equality() {
+    let lazy: Data = VecBuilder::new().push_lazy(|| "Mage").into();
+    let eager: Data = VecBuilder::new().push("Mage").into();
+
+    assert_eq!(eager, lazy);
+}
+
+#[test]
+fn test_lazy_patch_leaves_copies() {
+    let mut data: Data = HashBuilder::new()
+        .insert_lazy("hero", || HashBuilder::new().insert("name", "Anduin"))
+        .into();
+    let copy = data.clone();
+
+    *data.get_path_mut("hero.name").unwrap() = Data::from("Jaina");
+    assert_eq!(Some(&Data::from("Jaina")), data.get_path("hero.name"));
+    assert_eq!(Some(&Data::from("Anduin")), copy.get_path("hero.name"));
+}
@@ -0,0 +1,68 @@
+extern crate rustache;
+extern crate rustc_serialize;
+
+mod common;
+
+use rustache::{HashBuilder, Renderer, VecBuilder};
+use rustc_serialize::json::Json;
+
+use common::{render, render_with};
+
+fn product<'a>() -> HashBuilder<'a> {
+    HashBuilder::new()
+        .insert("name", "Thunderfury")
+        .insert("images", VecBuilder::new()
+            .push(HashBuilder::new().insert("url", "/img/front.png").insert("alt", "<front>"))
+            .push(HashBuilder::new().insert("url", "/img/back.png").insert("alt", "back")))
+        .insert("sizes", VecBuilder::new()
+            .push(VecBuilder::new().push("S").push("M"))
+            .push(VecBuilder::new().push("L")))
+}
+
+#[test]
+fn test_list_indexes_interpolation() {
+    assert_eq!("/img/front.png &lt;front&gt; <front> back",
+               render(&product(),
+                      "{{ images.0.url }} {{ images.0.alt }} {{{ images.0.alt }}} {{& images.1.alt }}"));
+    assert_eq!("M L", render(&product(), "{{ sizes.0.1 }} {{ sizes.1.0 }}"));
+}
+
+#[test]
+fn test_list_indexes_sections() {
+    assert_eq!("/img/back.png: Thunderfury",
+               render(&product(), "{{#images.1}}{{ url }}: {{ name }}{{/images.1}}"));
+    assert_eq!("no third image",
+               render(&product(), "{{#images.2}}{{ url }}{{/images.2}}{{^images.2}}no third image{{/images.2}}"));
+}
+
+#[test]
+fn test_list_indexes_out_of_range_and_not_lists() {
+    assert_eq!("<><><>", render(&product(), "<{{ images.5.url }}><{{ name.0 }}><{{ images.-1.url }}>"));
+}
+
+#[test]
+fn test_list_indexes_in_sections() {
+    let data = HashBuilder::new()
+        .insert("products", VecBuilder::new().push(product()).push(product().insert("name", "Ashbringer")));
+
+    assert_eq!("Thunderfury /img/front.png, Ashbringer /img/front.png, ",
+               render(&data, "{{#products}}{{ name }} {{ images.0.url }}, {{/products}}"));
+    assert_eq!("Ashbringer", render(&data, "{{ products.1.name }}"));
+}
+
+#[test]
+fn test_list_indexes_json() {
+    let data = Json::from_str(r#"{"rows": [{"id": 1}, {"id": 2, "tags": ["new"]}]}"#).unwrap();
+
+    assert_eq!("1 2 new", render(&data, "{{ rows.0.id }} {{#rows.1}}{{ id }} {{ tags.0 }}{{/rows.1}}"));
+}
+
+#[test]
+fn test_list_indexes_with_filters_and_context_paths() {
+    let renderer = Renderer::new().enable_filters().enable_context_paths();
+
+    assert_eq!("/IMG/BACK.PNG 7 7 ",
+               render_with(&renderer,
+                           &product(),
+                           "{{ images.1.url | upper }} {{#images}}{{ @root.images.0.alt | length }} {{/images}}"));
+}
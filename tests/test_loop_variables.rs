@@ -0,0 +1,74 @@
+extern crate rustache;
+
+mod common;
+
+use rustache::{HashBuilder, Renderer, VecBuilder};
+
+use common::render_with;
+
+fn render(data: &HashBuilder, template: &str) -> String {
+    render_with(&Renderer::new().enable_loop_variables(), data, template)
+}
+
+fn rows<'a>() -> VecBuilder<'a> {
+    VecBuilder::new()
+        .push(HashBuilder::new().insert("name", "Mage"))
+        .push(HashBuilder::new().insert("name", "Druid"))
+        .push(HashBuilder::new().insert("name", "Rogue"))
+}
+
+#[test]
+fn test_loop_variables_positions() {
+    let data = HashBuilder::new().insert("rows", rows());
+
+    assert_eq!("0/1/3 Mage, 1/2/3 Druid, 2/3/3 Rogue",
+               render(&data,
+                      "{{#rows}}{{@index}}/{{@index1}}/{{@length}} {{name}}{{^@last}}, {{/@last}}{{/rows}}"));
+}
+
+#[test]
+fn test_loop_variables_first() {
+    let data = HashBuilder::new().insert("rows", rows());
+
+    assert_eq!("<b>Mage</b> Druid Rogue ",
+               render(&data,
+                      "{{#rows}}{{#@first}}<b>{{/@first}}{{name}}{{#@first}}</b>{{/@first}} {{/rows}}"));
+}
+
+#[test]
+fn test_loop_variables_nested() {
+    let data = HashBuilder::new().insert("teams", VecBuilder::new()
+        .push(HashBuilder::new().insert("rows", rows()))
+        .push(HashBuilder::new().insert("rows", VecBuilder::new()
+            .push(HashBuilder::new().insert("name", "Priest")))));
+
+    assert_eq!("0: 0 1 2 | 1: 0 | ",
+               render(&data, "{{#teams}}{{@index}}: {{#rows}}{{@index}}{{^@last}} {{/@last}}{{/rows}} | {{/teams}}"));
+}
+
+#[test]
+fn test_loop_variables_stream() {
+    let data = HashBuilder::new()
+        .insert_stream("rows", (1..4).map(|id| HashBuilder::new().insert("id", id)));
+
+    assert_eq!("1 of 3, 2 of 3, 3 of 3.",
+               render(&data, "{{#rows}}{{id}} of {{@length}}{{#@last}}.{{/@last}}{{^@last}}, {{/@last}}{{/rows}}"));
+}
+
+#[test]
+fn test_loop_variables_off_by_default() {
+    let data = HashBuilder::new().insert("rows", rows());
+
+    assert_eq!("Mage Druid Rogue ", common::render(&data, "{{#rows}}{{@index}}{{name}} {{/rows}}"));
+}
+
+#[test]
+fn test_loop_variables_scalar_items() {
+    let names: Vec<&str> = vec!["Mage", "Druid", "Rogue"];
+    let data = HashBuilder::new().insert("names", names.into_iter().collect::<VecBuilder>());
+
+    assert_eq!("Mage, Druid, Rogue",
+               render(&data, "{{#names}}{{.}}{{^@last}}, {{/@last}}{{/names}}"));
+    assert_eq!("0:Mage 1:Druid 2:Rogue ",
+               render(&data, "{{#names}}{{@index}}:{{.}} {{/names}}"));
+}
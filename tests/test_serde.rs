@@ -0,0 +1,155 @@
+#![cfg(feature = "serde")]
+
+extern crate rustache;
+#[macro_use]
+extern crate serde_derive;
+
+mod common;
+
+use std::collections::BTreeMap;
+
+use rustache::{Data, HashBuilder, VecBuilder};
+
+use common::render;
+
+#[derive(Serialize)]
+struct Card {
+    name: String,
+    cost: u8,
+}
+
+#[derive(Serialize)]
+struct Hero {
+    name: String,
+    title: Option<String>,
+    level: i64,
+    health: f64,
+    alive: bool,
+    cards: Vec<Card>,
+}
+
+#[derive(Serialize)]
+enum Rarity {
+    Common,
+    Legendary(String),
+    Epic { glow: bool },
+}
+
+fn hero() -> Hero {
+    Hero {
+        name: "Anduin".to_string(),
+        title: None,
+        level: 10,
+        health: 27.5,
+        alive: true,
+        cards: vec![Card {
+                        name: "Heal".to_string(),
+                        cost: 1,
+                    },
+                    Card {
+                        name: "Smite".to_string(),
+                        cost: 2,
+                    }],
+    }
+}
+
+#[test]
+fn test_serialize_struct() {
+    let data = rustache::to_data(&hero()).unwrap();
+
+    assert_eq!("Anduin 10 27.5",
+               render(&data, "{{ name }} {{ level }} {{ health }}"));
+}
+
+#[test]
+fn test_serialize_bool_and_option() {
+    let data = rustache::to_data(&hero()).unwrap();
+
+    assert_eq!("alive untitled",
+               render(&data,
+                      "{{#alive}}alive{{/alive}} {{#title}}{{title}}{{/title}}{{^title}}untitled{{/title}}"));
+}
+
+#[test]
+fn test_serialize_some() {
+    let mut hero = hero();
+    hero.title = Some("Prophet".to_string());
+    let data = rustache::to_data(&hero).unwrap();
+
+    assert_eq!("Prophet", render(&data, "{{#title}}{{title}}{{/title}}"));
+}
+
+#[test]
+fn test_serialize_seq() {
+    let data = rustache::to_data(&hero()).unwrap();
+
+    assert_eq!("Heal (1) Smite (2) ",
+               render(&data, "{{#cards}}{{ name }} ({{ cost }}) {{/cards}}"));
+}
+
+#[test]
+fn test_serialize_map() {
+    let mut map = BTreeMap::new();
+    map.insert(1, "one");
+    map.insert(2, "two");
+    let data = rustache::to_data(&map).unwrap();
+
+    assert_eq!("one two", render(&data, "{{ 1 }} {{ 2 }}"));
+}
+
+#[test]
+fn test_serialize_large_integers() {
+    let mut map = BTreeMap::new();
+    map.insert("big", u64::MAX);
+    let data = rustache::to_data(&map).unwrap();
+
+    assert_eq!("18446744073709551615", render(&data, "{{ big }}"));
+}
+
+#[test]
+fn test_serialize_enum() {
+    assert_eq!(Data::from("Common"),
+               rustache::to_data(&Rarity::Common).unwrap());
+
+    let mut map = BTreeMap::new();
+    map.insert("legendary", Rarity::Legendary("Hogger".to_string()));
+    map.insert("epic", Rarity::Epic { glow: true });
+    let data = rustache::to_data(&map).unwrap();
+
+    assert_eq!("Hogger shiny",
+               render(&data,
+                      "{{ legendary.Legendary }} {{#epic}}{{#Epic}}{{#glow}}shiny{{/glow}}{{/Epic}}{{/epic}}"));
+}
+
+#[test]
+fn test_serialize_invalid_key() {
+    let mut map = BTreeMap::new();
+    map.insert(vec![1], "one");
+
+    assert!(rustache::to_data(&map).is_err());
+}
+
+#[test]
+fn test_render_non_hash() {
+    let data = rustache::to_data(&vec![1, 2]).unwrap();
+
+    assert_eq!("<>", render(&data, "<{{ a }}>"));
+}
+
+#[test]
+fn test_serialize_data_round_trip() {
+    let data = HashBuilder::new()
+        .insert("name", "Anduin")
+        .insert("wide", u64::MAX)
+        .insert("cards", VecBuilder::new().push("Heal").push(None::<String>))
+        .insert_fn("shout", |text| text.to_uppercase());
+
+    let expected = Data::from(HashBuilder::new()
+        .insert("name", "Anduin")
+        .insert("wide", u64::MAX)
+        .insert("cards", VecBuilder::new().push("Heal").push(None::<String>))
+        .insert("shout", "<lambda>"));
+
+    assert_eq!(expected, rustache::to_data(&data).unwrap());
+    assert_eq!(expected, rustache::to_data(&Data::from(data)).unwrap());
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "serde_json")]
+
+extern crate rustache;
+extern crate serde_json;
+
+mod common;
+
+use rustache::Renderer;
+use serde_json::Value;
+
+use common::{render, render_with};
+
+#[test]
+fn test_serde_json_interpolation() {
+    let data: Value = serde_json::from_str(r#"{"name": "Bob", "age": 42, "height": 1.8,
+                                              "admin": false, "bio": "<b>hi</b>"}"#)
+        .unwrap();
+
+    assert_eq!("Bob 42 1.8 false &lt;b&gt;hi&lt;/b&gt; <b>hi</b>",
+               render(&data,
+                      "{{ name }} {{ age }} {{ height }} {{ admin }} {{ bio }} {{{ bio }}}"));
+}
+
+#[test]
+fn test_serde_json_sections() {
+    let data: Value = serde_json::from_str(r#"{"admin": false, "user": {"name": "Bob"},
+                                              "missing": null, "empty": []}"#)
+        .unwrap();
+
+    assert_eq!("not admin Bob no value nothing",
+               render(&data,
+                      "{{#admin}}admin{{/admin}}{{^admin}}not admin{{/admin}} \
+                       {{#user}}{{name}}{{/user}} \
+                       {{^missing}}no value{{/missing}} \
+                       {{^empty}}nothing{{/empty}}"));
+}
+
+#[test]
+fn test_serde_json_list_of_objects() {
+    let data: Value = serde_json::from_str(r#"{"repo": [{"name": "resque"}, {"name": "hub"}]}"#)
+        .unwrap();
+
+    assert_eq!("<b>resque</b><b>hub</b>",
+               render(&data, "{{#repo}}<b>{{name}}</b>{{/repo}}"));
+}
+
+#[test]
+fn test_serde_json_keeps_numbers_in_arrays() {
+    let data: Value = serde_json::from_str(r#"{"numbers": [1, 2.5, 3], "none": null}"#).unwrap();
+
+    assert_eq!("12.53 <>", render(&data, "{{ numbers }} <{{ none }}>"));
+}
+
+#[test]
+fn test_serde_json_dotted_names() {
+    let data: Value = serde_json::from_str(r#"{"a": {"b": {"c": "deep"}}}"#).unwrap();
+
+    assert_eq!("deep", render(&data, "{{ a.b.c }}"));
+}
+
+#[test]
+fn test_serde_json_non_object_roots() {
+    let list: Value = serde_json::from_str("[1, 2, 3]").unwrap();
+    let text: Value = serde_json::from_str(r#""hello""#).unwrap();
+    let null: Value = serde_json::from_str("null").unwrap();
+
+    assert_eq!("<>", render(&list, "<{{ a }}>"));
+    assert_eq!("<1, 2, 3, >", render(&list, "<{{#.}}{{.}}, {{/.}}>"));
+    assert_eq!("<hello>", render(&text, "<{{ a }}{{#a}}a{{/a}}{{.}}>"));
+    assert_eq!("<hello!>", render(&text, "<{{#.}}{{.}}!{{/.}}>"));
+    assert_eq!("<none>", render(&null, "<{{^a}}none{{/a}}{{.}}>"));
+    assert_eq!("<none>", render(&null, "<{{#.}}some{{/.}}{{^.}}none{{/.}}>"));
+}
+
+#[test]
+#[cfg(feature = "preserve_order")]
+fn test_serde_json_objects_keep_their_order() {
+    let data: Value = serde_json::from_str(r#"{"stats": {"zeta": 1, "alpha": 2, "mu": 3}}"#).unwrap();
+    let renderer = Renderer::new().enable_hash_iteration();
+
+    assert_eq!("zeta alpha mu ",
+               render_with(&renderer, &data, "{{#stats.@entries}}{{@key}} {{/stats.@entries}}"));
+}
+
+#[test]
+#[cfg(not(feature = "preserve_order"))]
+fn test_serde_json_objects_are_sorted() {
+    let data: Value = serde_json::from_str(r#"{"stats": {"zeta": 1, "alpha": 2, "mu": 3}}"#).unwrap();
+    let renderer = Renderer::new().enable_hash_iteration();
+
+    assert_eq!("alpha mu zeta ",
+               render_with(&renderer, &data, "{{#stats.@entries}}{{@key}} {{/stats.@entries}}"));
+}
@@ -0,0 +1,67 @@
+extern crate rustache;
+
+use std::io::Cursor;
+use std::sync::Arc;
+use std::thread;
+
+use rustache::{Data, HashBuilder, Render, VecBuilder};
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn test_shared_data_is_send_and_sync() {
+    assert_send_sync::<Data<'static>>();
+    assert_send_sync::<HashBuilder<'static>>();
+    assert_send_sync::<VecBuilder<'static>>();
+}
+
+#[test]
+fn test_shared_static_builder_across_threads() {
+    let data: Arc<HashBuilder<'static>> = Arc::new(HashBuilder::new()
+        .insert("name", "Anduin")
+        .insert_fn("shout", |text| text.to_uppercase())
+        .insert_lazy("cards", || VecBuilder::new().push("Heal").push("Smite")));
+
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let data = data.clone();
+            thread::spawn(move || {
+                let mut rv = Cursor::new(Vec::new());
+                data.render("{{#shout}}hello{{/shout}} {{ name }} {{ cards }}", &mut rv).unwrap();
+                String::from_utf8(rv.into_inner()).unwrap()
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        assert_eq!("HELLO Anduin HealSmite", handle.join().unwrap());
+    }
+}
+
+#[test]
+fn test_shared_lambda_in_vector() {
+    let data = HashBuilder::new()
+        .insert("items",
+                VecBuilder::new().push(HashBuilder::new().insert_fn("wrap", |text| format!("<{}>", text))));
+
+    let mut rv = Cursor::new(Vec::new());
+    data.render("{{#items}}{{#wrap}}x{{/wrap}}{{/items}}", &mut rv).unwrap();
+    assert_eq!("<x>", String::from_utf8(rv.into_inner()).unwrap());
+}
+
+#[test]
+fn test_shared_borrowed_lambda() {
+    let mut calls = 0;
+    {
+        let mut f = |text: String| {
+            calls += 1;
+            text.to_uppercase()
+        };
+        let data = HashBuilder::new().insert_lambda("shout", &mut f);
+
+        let mut rv = Cursor::new(Vec::new());
+        data.render("{{#shout}}hello{{/shout}} {{#shout}}again{{/shout}}", &mut rv).unwrap();
+        assert_eq!("HELLO AGAIN", String::from_utf8(rv.into_inner()).unwrap());
+    }
+    assert_eq!(2, calls);
+}
@@ -152,7 +152,6 @@ fn test_spec_inverted_missing_falsey() {
 //   template: '"{{^a.b.c}}Not Here{{/a.b.c}}" == ""'
 //   expected: '"" == ""'
 #[test]
-#[ignore]
 fn test_spec_truthy_dotted_names_valid_inverted_section_tags() {
     let data = HashBuilder::new()
         .insert("a", HashBuilder::new()
@@ -67,7 +67,6 @@ fn test_spec_lambdas_interpolation_expansion() {
 //     template: "{{= | | =}}\nHello, (|&lambda|)!"
 //     expected: "Hello, (|planet| => world)!"
 #[test]
-#[ignore]
 fn test_spec_lambdas_interpolation_alternate_delimeters() {
     let mut f = |_| {
         "|planet| => {{planet}}".to_string()
@@ -212,7 +211,6 @@ fn test_spec_lambdas_section_expansion() {
 //     template: "{{= | | =}}<|#lambda|-|/lambda|>"
 //     expected: "<-{{planet}} => Earth->"
 #[test]
-#[ignore]
 fn test_spec_lambdas_section_alternate_delimeters() {
     let mut f = |txt: String| {
         let mut result = txt.to_string();
@@ -284,4 +282,3 @@ fn test_spec_lambdas_inverted_section() {
 
     assert_eq!("<>".to_string(), String::from_utf8(rv.into_inner()).unwrap());
 }
-
@@ -275,22 +275,21 @@ fn test_spec_sections_failed_context_lookups_are_falsy() {
 //       list: [ 'a', 'b', 'c', 'd', 'e' ]
 //     template: '"{{#list}}({{.}}){{/list}}"'
 //     expected: '"(a)(b)(c)(d)(e)"'
-// #[test]
-// fn test_spec_sections_implicit_iterators_directly_interpolate_strings() {
-//     let data = HashBuilder::new()
-//         .insert_vector("list", |builder| {
-//             builder
-//                 .push_string("a")
-//                 .push_string("b")
-//                 .push_string("c")
-//                 .push_string("d")
-//                 .push_string("e")
-//         });
-//     let mut rv = Cursor::new(Vec::new());
-//     data.render("{{#list}}({{.}}){{/list}}", &mut rv).unwrap();
+#[test]
+fn test_spec_sections_implicit_iterators_directly_interpolate_strings() {
+    let data = HashBuilder::new()
+        .insert("list", VecBuilder::new()
+                .push("a")
+                .push("b")
+                .push("c")
+                .push("d")
+                .push("e")
+        );
+    let mut rv = Cursor::new(Vec::new());
+    data.render("{{#list}}({{.}}){{/list}}", &mut rv).unwrap();
 
-//     assert_eq!("(a)(b)(c)(d)(e)".to_string(), String::from_utf8(rv.into_inner()).unwrap());
-// }
+    assert_eq!("(a)(b)(c)(d)(e)".to_string(), String::from_utf8(rv.into_inner()).unwrap());
+}
 
 //   - name: Implicit Iterator - Integer
 //     desc: Implicit iterators should cast integers to strings and interpolate.
@@ -298,22 +297,21 @@ fn test_spec_sections_failed_context_lookups_are_falsy() {
 //       list: [ 1, 2, 3, 4, 5 ]
 //     template: '"{{#list}}({{.}}){{/list}}"'
 //     expected: '"(1)(2)(3)(4)(5)"'
-// #[test]
-// fn test_spec_sections_implicit_iterators_directly_interpolate_integers() {
-//     let data = HashBuilder::new()
-//         .insert_vector("list", |builder| {
-//             builder
-//                 .push_int(1)
-//                 .push_int(2)
-//                 .push_int(3)
-//                 .push_int(4)
-//                 .push_int(5)
-//         });
-//     let mut rv = Cursor::new(Vec::new());
-//     data.render("{{#list}}({{.}}){{/list}}", &mut rv).unwrap();
+#[test]
+fn test_spec_sections_implicit_iterators_directly_interpolate_integers() {
+    let data = HashBuilder::new()
+        .insert("list", VecBuilder::new()
+                .push(1)
+                .push(2)
+                .push(3)
+                .push(4)
+                .push(5)
+        );
+    let mut rv = Cursor::new(Vec::new());
+    data.render("{{#list}}({{.}}){{/list}}", &mut rv).unwrap();
 
-//     assert_eq!("(1)(2)(3)(4)(5)".to_string(), String::from_utf8(rv.into_inner()).unwrap());
-// }
+    assert_eq!("(1)(2)(3)(4)(5)".to_string(), String::from_utf8(rv.into_inner()).unwrap());
+}
 
 //   - name: Implicit Iterator - Decimal
 //     desc: Implicit iterators should cast decimals to strings and interpolate.
@@ -321,43 +319,40 @@ fn test_spec_sections_failed_context_lookups_are_falsy() {
 //       list: [ 1.10, 2.20, 3.30, 4.40, 5.50 ]
 //     template: '"{{#list}}({{.}}){{/list}}"'
 //     expected: '"(1.1)(2.2)(3.3)(4.4)(5.5)"'
-// #[test]
-// fn test_spec_sections_implicit_iterators_directly_interpolate_floats() {
-//     let data = HashBuilder::new()
-//         .insert_vector("list", |builder| {
-//             builder
-//                 .push_float(1.10)
-//                 .push_float(2.20)
-//                 .push_float(3.30)
-//                 .push_float(4.40)
-//                 .push_float(5.50)
-//         });
-//     let mut rv = Cursor::new(Vec::new());
-//     data.render("{{#list}}({{.}}){{/list}}", &mut rv).unwrap();
+#[test]
+fn test_spec_sections_implicit_iterators_directly_interpolate_floats() {
+    let data = HashBuilder::new()
+        .insert("list", VecBuilder::new()
+                .push(1.10)
+                .push(2.20)
+                .push(3.30)
+                .push(4.40)
+                .push(5.50)
+        );
+    let mut rv = Cursor::new(Vec::new());
+    data.render("{{#list}}({{.}}){{/list}}", &mut rv).unwrap();
 
-//     assert_eq!("(1.1)(2.2)(3.3)(4.4)(5.5)".to_string(), String::from_utf8(rv.into_inner()).unwrap());
-// }
+    assert_eq!("(1.1)(2.2)(3.3)(4.4)(5.5)".to_string(), String::from_utf8(rv.into_inner()).unwrap());
+}
 
 //   - name: Dotted Names - Truthy
 //     desc: Dotted names should be valid for Section tags.
 //     data: { a: { b: { c: true } } }
 //     template: '"{{#a.b.c}}Here{{/a.b.c}}" == "Here"'
 //     expected: '"Here" == "Here"'
-// #[test]
-// fn test_spec_sections_truthy_dotted_names_are_valid_section_tags() {
-//     let data = HashBuilder::new()
-//         .insert_hash("a", |builder| {
-//             builder
-//                 .insert_hash("b", |builder| {
-//                     builder
-//                         .insert_bool("c", true)
-//             })
-//         });
-//     let mut rv = Cursor::new(Vec::new());
-//     data.render("'{{#a.b.c}}Here{{/a.b.c}}' == 'Here'", &mut rv).unwrap();
+#[test]
+fn test_spec_sections_truthy_dotted_names_are_valid_section_tags() {
+    let data = HashBuilder::new()
+        .insert("a", HashBuilder::new()
+                .insert("b", HashBuilder::new()
+                        .insert("c", true)
+            )
+        );
+    let mut rv = Cursor::new(Vec::new());
+    data.render("'{{#a.b.c}}Here{{/a.b.c}}' == 'Here'", &mut rv).unwrap();
 
-//     assert_eq!("'Here' == 'Here'".to_string(), String::from_utf8(rv.into_inner()).unwrap());
-// }
+    assert_eq!("'Here' == 'Here'".to_string(), String::from_utf8(rv.into_inner()).unwrap());
+}
 
 //   - name: Dotted Names - Falsy
 //     desc: Dotted names should be valid for Section tags.
@@ -0,0 +1,93 @@
+extern crate rustache;
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::io::{self, Write};
+
+use rustache::{HashBuilder, Render, VecBuilder};
+
+use common::render;
+
+#[test]
+fn test_stream_section() {
+    let data = HashBuilder::new()
+        .insert_stream("rows", (1..4).map(|id| HashBuilder::new().insert("id", id)));
+
+    assert_eq!("1,2,3,", render(&data, "{{#rows}}{{ id }},{{/rows}}"));
+}
+
+#[test]
+fn test_stream_is_consumed_while_rendering() {
+    let produced = AtomicUsize::new(0);
+    let data = HashBuilder::new().insert_stream("rows",
+                                                (0..3).map(|id| {
+                                                    produced.fetch_add(1, Ordering::SeqCst);
+                                                    HashBuilder::new().insert("id", id)
+                                                }));
+
+    assert_eq!(0, produced.load(Ordering::SeqCst));
+    assert_eq!("012", render(&data, "{{#rows}}{{ id }}{{/rows}}"));
+    assert_eq!(3, produced.load(Ordering::SeqCst));
+}
+
+#[test]
+fn test_stream_empty_is_falsy() {
+    let data = HashBuilder::new()
+        .insert_stream("rows", Vec::<HashBuilder>::new())
+        .insert_stream("more", vec![HashBuilder::new()]);
+
+    assert_eq!("none some",
+               render(&data,
+                      "{{#rows}}row{{/rows}}{{^rows}}none{{/rows}} \
+                       {{^more}}none{{/more}}{{#more}}some{{/more}}"));
+}
+
+#[test]
+fn test_stream_renders_once() {
+    let data = HashBuilder::new().insert_stream("rows", vec!["a", "b"]);
+
+    assert_eq!("ab", render(&data, "{{ rows }}{{ rows }}"));
+}
+
+#[test]
+fn test_stream_nested_in_vector() {
+    let data = HashBuilder::new()
+        .insert("lists", VecBuilder::new().push_stream(vec!["x", "y"]));
+
+    assert_eq!("xy", render(&data, "{{ lists }}"));
+}
+
+#[test]
+fn test_stream_nested_use_of_same_stream() {
+    let data = HashBuilder::new()
+        .insert_stream("rows", (0..2).map(|id| HashBuilder::new().insert("id", id)));
+
+    assert_eq!("0[]1[]",
+               render(&data, "{{#rows}}{{ id }}[{{#rows}}inner{{/rows}}]{{/rows}}"));
+}
+
+// a writer that only counts bytes, to render a large stream without
+// keeping the output around
+struct Counter(usize);
+
+impl Write for Counter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_stream_large() {
+    let data = HashBuilder::new()
+        .insert_stream("rows", (0..100000).map(|_| HashBuilder::new().insert("cell", "ab")));
+    let mut out = Counter(0);
+    data.render("{{#rows}}{{ cell }};{{/rows}}", &mut out).unwrap();
+
+    assert_eq!(300000, out.0);
+}
@@ -0,0 +1,76 @@
+extern crate rustache;
+
+mod common;
+
+use rustache::{Context, HashBuilder, Renderer, Truthiness, VecBuilder};
+
+use common::render_with;
+
+fn shown(renderer: &Renderer, name: &str) -> String {
+    let data = HashBuilder::new()
+        .insert("empty", "")
+        .insert("zero", 0)
+        .insert("float_zero", 0.0)
+        .insert("nan", f64::NAN)
+        .insert("zero_text", "0")
+        .insert("no", false)
+        .insert("none", VecBuilder::new())
+        .insert("blank", HashBuilder::new())
+        .insert("name", "Anduin");
+    let template = format!("{{{{#{0}}}}}yes{{{{/{0}}}}}{{{{^{0}}}}}no{{{{/{0}}}}}", name);
+    render_with(renderer, &data, &template)
+}
+
+#[test]
+fn test_truthiness_spec_by_default() {
+    let renderer = Renderer::new();
+
+    assert_eq!("yes", shown(&renderer, "empty"));
+    assert_eq!("yes", shown(&renderer, "zero"));
+    assert_eq!("yes", shown(&renderer, "blank"));
+    assert_eq!("no", shown(&renderer, "no"));
+    assert_eq!("no", shown(&renderer, "none"));
+    assert_eq!("no", shown(&renderer, "missing"));
+}
+
+#[test]
+fn test_truthiness_javascript() {
+    let renderer = Renderer::new().truthiness(Truthiness::JavaScript);
+
+    assert_eq!("no", shown(&renderer, "empty"));
+    assert_eq!("no", shown(&renderer, "zero"));
+    assert_eq!("no", shown(&renderer, "float_zero"));
+    assert_eq!("no", shown(&renderer, "nan"));
+    assert_eq!("no", shown(&renderer, "none"));
+    assert_eq!("yes", shown(&renderer, "zero_text"));
+    assert_eq!("yes", shown(&renderer, "blank"));
+    assert_eq!("yes", shown(&renderer, "name"));
+}
+
+#[test]
+fn test_truthiness_custom() {
+    let renderer = Renderer::new().truthy_when(|data: &dyn Context| {
+        data.is_truthy() && data.entries().map_or(true, |entries| !entries.is_empty())
+    });
+
+    assert_eq!("no", shown(&renderer, "blank"));
+    assert_eq!("no", shown(&renderer, "no"));
+    assert_eq!("no", shown(&renderer, "missing"));
+    assert_eq!("yes", shown(&renderer, "empty"));
+}
+
+#[test]
+fn test_truthiness_in_nested_sections() {
+    let data = HashBuilder::new()
+        .insert("inbox", HashBuilder::new().insert("unread", 0).insert("owner", "Jaina"))
+        .insert("messages", VecBuilder::new()
+            .push(HashBuilder::new().insert("subject", "Hi").insert("flag", ""))
+            .push(HashBuilder::new().insert("subject", "Re: Hi").insert("flag", "!")));
+    let template = "{{#inbox}}{{owner}}{{#unread}}: {{unread}} new{{/unread}}\
+                    {{^unread}}: all read{{/unread}}{{/inbox}} \
+                    {{#messages}}{{subject}}{{#flag}} {{flag}}{{/flag}};{{/messages}}";
+
+    assert_eq!("Jaina: 0 new Hi ;Re: Hi !;", render_with(&Renderer::new(), &data, template));
+    assert_eq!("Jaina: all read Hi;Re: Hi !;",
+               render_with(&Renderer::new().truthiness(Truthiness::JavaScript), &data, template));
+}
@@ -0,0 +1,99 @@
+extern crate rustache;
+#[macro_use]
+extern crate rustache_derive;
+
+use rustache::TypedTemplate;
+use std::io::Cursor;
+
+#[derive(Mustache)]
+struct Card {
+    name: String,
+    cost: i32,
+}
+
+#[derive(Mustache)]
+struct Stats {
+    alive: bool,
+}
+
+#[derive(Mustache)]
+#[mustache(template = "test_data/typed_hero.html")]
+struct Hero {
+    name: String,
+    title: Option<String>,
+    cards: Vec<Card>,
+    stats: Stats,
+}
+
+#[test]
+fn test_typed_template_source() {
+    assert!(Hero::TEMPLATE.starts_with("<h1>{{name}}"));
+}
+
+#[test]
+fn test_typed_template_render() {
+    let hero = Hero {
+        name: "Jaina".to_string(),
+        title: Some("Lord Admiral".to_string()),
+        cards: vec![Card { name: "Fireball".to_string(), cost: 4 },
+                    Card { name: "Frostbolt".to_string(), cost: 2 }],
+        stats: Stats { alive: true },
+    };
+    let mut rv = Cursor::new(Vec::new());
+    hero.render(&mut rv).unwrap();
+
+    assert_eq!("<h1>Jaina, Lord Admiral</h1>\n<ul>\n\
+                <li>Fireball (4)</li><li>Frostbolt (2)</li>\n</ul>\nalive: true\n"
+                   .to_string(),
+               String::from_utf8(rv.into_inner()).unwrap());
+}
+
+#[test]
+fn test_typed_template_render_falsy() {
+    let hero = Hero {
+        name: "Arthas".to_string(),
+        title: None,
+        cards: vec![],
+        stats: Stats { alive: false },
+    };
+    let mut rv = Cursor::new(Vec::new());
+    hero.render(&mut rv).unwrap();
+
+    assert_eq!("<h1>Arthas</h1>\n<ul>\n\n</ul>\nalive: false\n".to_string(),
+               String::from_utf8(rv.into_inner()).unwrap());
+}
+
+#[derive(Mustache)]
+enum Status {
+    #[mustache(rename = "online")]
+    Online,
+}
+
+#[derive(Mustache)]
+enum Rank {
+    Knight { order: String },
+}
+
+// enums aren't hashes, so they can be used as values and what is used
+// inside sections over them isn't checked
+#[derive(Mustache)]
+#[mustache(template = "test_data/typed_member.html")]
+struct Member {
+    name: String,
+    status: Status,
+    rank: Option<Rank>,
+}
+
+#[test]
+fn test_typed_template_enums() {
+    let member = Member {
+        name: "Tirion".to_string(),
+        status: Status::Online,
+        rank: Some(Rank::Knight { order: "the Silver Hand".to_string() }),
+    };
+    let mut rv = Cursor::new(Vec::new());
+    member.render(&mut rv).unwrap();
+
+    assert_eq!("Tirion is online, knight of the Silver Hand\n".to_string(),
+               String::from_utf8(rv.into_inner()).unwrap());
+}